@@ -0,0 +1,41 @@
+//! Renders `cards.md` and the ability checklist of `readme.md` from the card registry.
+//!
+//! `cargo run -p ld49-engine --bin cards [--check]`
+//!
+//! `--check` writes nothing and fails if the files are out of date.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+use ld49_engine::docs::{cards_markdown, update_readme};
+
+fn root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("..")
+}
+
+fn run(check: bool) -> Result<(), String> {
+    let cards_path = root().join("cards.md");
+    let readme_path = root().join("readme.md");
+    let readme = fs::read_to_string(&readme_path).map_err(|e| format!("Cannot read {}: {}", readme_path.display(), e))?;
+
+    let files = [(cards_path, cards_markdown()), (readme_path, update_readme(&readme)?)];
+    for (path, contents) in files.iter() {
+        if check {
+            if fs::read_to_string(path).ok().as_deref() != Some(contents.as_str()) {
+                return Err(format!("{} is out of date, run `cargo run -p ld49-engine --bin cards`", path.display()));
+            }
+        } else {
+            fs::write(path, contents).map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+fn main() {
+    let check = std::env::args().skip(1).any(|arg| arg == "--check");
+    if let Err(e) = run(check) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,62 @@
+//! Estimates how often a board beats another one.
+//!
+//! `cargo run -p ld49-engine --bin winrate -- "Mush2 1/3, Mush4" "Spid1 2/1, Spid2" [runs] [seed] [--left-to-right] [--damage <formula>] [--turn <turn>]`
+//!
+//! A board is a comma-separated list of cards, each one being a `BaseCards` variant
+//! optionally followed by its ATK/HP (base stats otherwise), `golden` and extra keywords (taunt, shield, venom).
+//!
+//! `--left-to-right` makes cards attack with `AttackOrder::LeftToRight`.
+//! `--damage` sets the `DamageFormula`: `rank-sum` (default), `rank-sum-shop-level`, `turn` or a flat number.
+//! `--turn` sets the turn of the fights (1 by default), for the `turn` formula.
+
+use std::process;
+
+use ld49_engine::card::parse_board;
+use ld49_engine::combat::{AttackOrder, CombatRules};
+use ld49_engine::winrate::estimate_win_rate;
+
+const DEFAULT_RUNS: u32 = 10000;
+
+fn run(args: &[String]) -> Result<(), String> {
+    let mut rules = CombatRules { turn: 1, ..Default::default() };
+    let mut positional = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--left-to-right" => rules.attack_order = AttackOrder::LeftToRight,
+            "--damage" => rules.damage = args.next().ok_or("Missing damage formula")?.parse()?,
+            "--turn" => rules.turn = match args.next() {
+                Some(turn) => turn.parse().map_err(|_| format!("Invalid turn {}", turn))?,
+                None => return Err("Missing turn".to_string()),
+            },
+            _ => positional.push(arg.clone()),
+        }
+    }
+    let args = positional;
+    if args.len() < 2 {
+        return Err("Usage: winrate <board 1> <board 2> [runs] [seed] [--left-to-right] [--damage <formula>] [--turn <turn>]".to_string());
+    }
+
+    let mut next_card_id = 0;
+    let board1 = parse_board(&args[0], &mut next_card_id)?;
+    let board2 = parse_board(&args[1], &mut next_card_id)?;
+    let runs = match args.get(2) {
+        Some(runs) => runs.parse().map_err(|_| format!("Invalid number of runs {}", runs))?,
+        None => DEFAULT_RUNS,
+    };
+    let seed = match args.get(3) {
+        Some(seed) => seed.parse().map_err(|_| format!("Invalid seed {}", seed))?,
+        None => 0,
+    };
+
+    println!("{}", estimate_win_rate(&board1, &board2, &rules, runs, seed));
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = run(&args) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
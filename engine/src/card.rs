@@ -0,0 +1,489 @@
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use derive_more::Display;
+use serde::{Deserialize, Serialize};
+use rand::Rng;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum BaseCards {
+    Mush1,
+    Mush2,
+    Mush3,
+    Mush4,
+    Mush5,
+    Mush6,
+    Mush7,
+    Mush8,
+
+    Merch1,
+    Merch2,
+    Merch3,
+    Merch4,
+    Merch5,
+    Merch6,
+    Merch7,
+    Merch8,
+
+    Spid1,
+    Spid2,
+    Spid3,
+    Spid4,
+    Spid5,
+    Spid6,
+    Spid7,
+    Spid8,
+
+    Rob1,
+    Rob2,
+    Rob3,
+    Rob4,
+    Rob5,
+    Rob6,
+    Rob7,
+    Rob8,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Card {
+    pub base_card: BaseCards,
+    pub id: u32,
+    pub hp: u16,
+    pub atk: u16,
+    pub played: u8,
+    #[serde(default)]
+    pub keywords: Keywords,
+    /// Made of three copies: doubled base stats and stronger ability
+    #[serde(default)]
+    pub golden: bool,
+}
+
+/// Keywords honoured by the combat simulator.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keywords {
+    /// Enemies have to attack a card with Taunt
+    pub taunt: bool,
+    /// Absorbs the first hit taken in a fight
+    pub shield: bool,
+    /// Kills every card it deals damage to
+    pub venom: bool,
+}
+
+impl Keywords {
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut names = vec![];
+        if self.taunt { names.push("Taunt"); }
+        if self.shield { names.push("Shield"); }
+        if self.venom { names.push("Venom"); }
+        names
+    }
+}
+
+impl Default for Card {
+    fn default() -> Self {
+        Card {
+            base_card: BaseCards::Merch8,
+            id: 0,
+            hp: 0,
+            atk: 0,
+            played: 0,
+            keywords: Keywords::default(),
+            golden: false,
+        }
+    }
+}
+
+#[derive(Debug, Display, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+pub enum Abilities {
+    // Mushrooms
+    Slimy,
+    SweetScent,
+    ToxicSpores,
+    Sporocarp,
+    Roots,
+    Gigantism,
+    // Merchants
+    Sadism,
+    ExplodingArmour,
+    Pillage,
+    GoldMine,
+    Altruism,
+    Dexterity,
+    // Spiders
+    Cooperation,
+    Trap,
+    Multiplication,
+    Spawn,
+    Cannibalism,
+    // Robots
+    Replication,
+    Scanner,
+    Upgrade,
+    Glitch,
+    Upload,
+    Download,
+
+    // None or not at combat time
+    None,
+}
+
+impl Abilities {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Abilities::Slimy => "Slimy",
+            Abilities::SweetScent => "Sweet scent",
+            Abilities::ToxicSpores => "Toxic spores",
+            Abilities::Sporocarp => "Sporocarp",
+            Abilities::Roots => "Roots",
+            Abilities::Gigantism => "Gigantism",
+            Abilities::Sadism => "Sadism",
+            Abilities::ExplodingArmour => "Exploding armour",
+            Abilities::Pillage => "Pillage",
+            Abilities::GoldMine => "Gold mine",
+            Abilities::Altruism => "Altruism",
+            Abilities::Dexterity => "Dexterity",
+            Abilities::Cooperation => "Cooperation",
+            Abilities::Trap => "Trap",
+            Abilities::Multiplication => "Multiplication",
+            Abilities::Spawn => "Spawn",
+            Abilities::Cannibalism => "Cannibalism",
+            Abilities::Replication => "Replication",
+            Abilities::Scanner => "Scanner",
+            Abilities::Upgrade => "Upgrade",
+            Abilities::Glitch => "Glitch",
+            Abilities::Upload => "Upload",
+            Abilities::Download => "Download",
+            Abilities::None => "None",
+        }
+    }
+
+    /// Abilities resolved by the combat simulator, in `apply_effect` or, for Sadism and Dexterity, in `simulate_attack`
+    pub const COMBAT: [Abilities; 9] = [
+        Abilities::ToxicSpores, Abilities::Gigantism, Abilities::Sadism, Abilities::ExplodingArmour, Abilities::Pillage,
+        Abilities::Dexterity, Abilities::Trap, Abilities::Multiplication, Abilities::Glitch,
+    ];
+
+    /// Abilities resolved by the shop of the game, in `played_trigger`, `sold_trigger` and `display_ability_animation`
+    pub const SHOP: [Abilities; 14] = [
+        Abilities::Slimy, Abilities::SweetScent, Abilities::Sporocarp, Abilities::Roots,
+        Abilities::GoldMine, Abilities::Altruism,
+        Abilities::Cooperation, Abilities::Spawn, Abilities::Cannibalism,
+        Abilities::Replication, Abilities::Scanner, Abilities::Upgrade, Abilities::Upload, Abilities::Download,
+    ];
+
+    /// Whether the ability has its code, in the combat simulator or in the shop.
+    /// Abilities added to the cards before their code are in neither list.
+    pub fn implemented(&self) -> bool {
+        Abilities::COMBAT.contains(self) || Abilities::SHOP.contains(self)
+    }
+}
+
+#[derive(Debug, Display, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+pub enum Triggers {
+    // At the beginning of each turn
+    Turn,
+    // When this card is played
+    Played,
+    // When this card dies
+    Death,
+    // When this card attacks and survives
+    Survived,
+    // When this card attacks or is attacked
+    Hit,
+    // When this card kills
+    Kill,
+    // When this card is sold
+    Sold,
+    // Before the first attack of a combat
+    StartOfCombat,
+    // After the last attack of a combat
+    EndOfCombat,
+    // PASSIVE,
+    None,
+}
+
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Families {
+    Merchants,
+    Spiders,
+    Robots,
+    Mushrooms,
+}
+
+impl BaseCards {
+    pub const ALL: [BaseCards; 32] = [
+        BaseCards::Mush1, BaseCards::Mush2, BaseCards::Mush3, BaseCards::Mush4,
+        BaseCards::Mush5, BaseCards::Mush6, BaseCards::Mush7, BaseCards::Mush8,
+        BaseCards::Merch1, BaseCards::Merch2, BaseCards::Merch3, BaseCards::Merch4,
+        BaseCards::Merch5, BaseCards::Merch6, BaseCards::Merch7, BaseCards::Merch8,
+        BaseCards::Spid1, BaseCards::Spid2, BaseCards::Spid3, BaseCards::Spid4,
+        BaseCards::Spid5, BaseCards::Spid6, BaseCards::Spid7, BaseCards::Spid8,
+        BaseCards::Rob1, BaseCards::Rob2, BaseCards::Rob3, BaseCards::Rob4,
+        BaseCards::Rob5, BaseCards::Rob6, BaseCards::Rob7, BaseCards::Rob8,
+    ];
+
+    /// Definition of the card in the registry
+    pub fn definition(&self) -> &'static CardDefinition {
+        registry().get(*self)
+    }
+
+    pub fn name(&self) -> &'static str {
+        &self.definition().name
+    }
+
+    pub fn ability(&self) -> Abilities {
+        self.definition().ability
+    }
+
+    pub fn family(&self) -> Families {
+        self.definition().family
+    }
+
+    pub fn description(&self) -> &'static str {
+        &self.definition().description
+    }
+
+    pub fn keywords(&self) -> Keywords {
+        self.definition().keywords
+    }
+
+    pub fn rank(&self) -> u8 {
+        self.definition().rank
+    }
+
+    pub fn trigger(&self) -> Triggers {
+        self.definition().trigger
+    }
+
+    /// Path of the card art, relative to `assets/`
+    pub fn art(&self) -> &'static str {
+        &self.definition().art
+    }
+
+    pub fn random_spider_below<T: Rng>(rank: u8, rng: &mut T) -> Option<BaseCards> {
+        let spiders: Vec<BaseCards> = [
+            BaseCards::Spid1, BaseCards::Spid2, BaseCards::Spid3, BaseCards::Spid4,
+            BaseCards::Spid5, BaseCards::Spid6, BaseCards::Spid7, BaseCards::Spid8,
+        ].iter()
+            .filter(|spider| spider.rank() < rank)
+            .copied()
+            .collect();
+        if spiders.is_empty() {
+            return None;
+        }
+        Some(spiders[rng.gen_range(0..spiders.len())])
+    }
+}
+
+impl FromStr for BaseCards {
+    type Err = String;
+
+    /// Parses the variant name, e.g. `Mush2`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BaseCards::ALL.iter()
+            .copied()
+            .find(|base_card| format!("{:?}", base_card) == s)
+            .ok_or_else(|| format!("Unknown card {}", s))
+    }
+}
+
+impl Card {
+    pub fn new(card_type: BaseCards, id: u32) -> Self {
+        let definition = card_type.definition();
+        Card {
+            id,
+            base_card: card_type,
+            atk: definition.atk,
+            hp: definition.hp,
+            keywords: definition.keywords,
+            ..Default::default()
+        }
+    }
+
+    pub fn new_golden(card_type: BaseCards, id: u32) -> Self {
+        let card = Card::new(card_type, id);
+        Card { atk: 2 * card.atk, hp: 2 * card.hp, golden: true, ..card }
+    }
+
+    /// Golden card made of three copies of the same card, keeping the stats they gained.
+    pub fn merge(copies: &[Card; 3], id: u32) -> Self {
+        let base_card = copies[0].base_card;
+        let definition = base_card.definition();
+        let mut card = Card::new_golden(base_card, id);
+        for copy in copies.iter() {
+            card.atk += copy.atk.saturating_sub(definition.atk);
+            card.hp += copy.hp.saturating_sub(definition.hp);
+        }
+        card
+    }
+
+    /// How many times stronger the ability of the card is
+    pub fn magnitude(&self) -> u16 {
+        if self.golden { 2 } else { 1 }
+    }
+}
+
+/// Parses a comma-separated list of cards, e.g. `Mush2 1/3, Spid6 shield`.
+/// Each card is a `BaseCards` variant optionally followed by its ATK/HP (base stats otherwise)
+/// and extra keywords (taunt, shield, venom), or `golden`. Cards get ids from `next_card_id`.
+pub fn parse_board(board: &str, next_card_id: &mut u32) -> Result<Vec<Card>, String> {
+    let mut cards = vec![];
+    for card in board.split(',').map(str::trim).filter(|card| !card.is_empty()) {
+        let mut parts = card.split_whitespace();
+        let base_card: BaseCards = parts.next().unwrap_or_default().parse()?;
+        let parts: Vec<&str> = parts.collect();
+        let mut card = if parts.contains(&"golden") {
+            Card::new_golden(base_card, *next_card_id)
+        } else {
+            Card::new(base_card, *next_card_id)
+        };
+        *next_card_id += 1;
+
+        for part in parts {
+            match part {
+                "golden" => {}
+                "taunt" => card.keywords.taunt = true,
+                "shield" => card.keywords.shield = true,
+                "venom" => card.keywords.venom = true,
+                stats => {
+                    let (atk, hp) = stats.split_once('/').ok_or(format!("Expected ATK/HP or a keyword, got {}", stats))?;
+                    card.atk = atk.parse().map_err(|_| format!("Invalid ATK {}", atk))?;
+                    card.hp = hp.parse().map_err(|_| format!("Invalid HP {}", hp))?;
+                }
+            }
+        }
+        cards.push(card);
+    }
+    Ok(cards)
+}
+
+/// Cards are ranked from 1 to `MAX_RANK`, one rank for each shop level
+pub const MAX_RANK: u8 = 6;
+
+/// Everything about a card but its abilities, which are implemented in the code.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CardDefinition {
+    pub id: BaseCards,
+    pub name: String,
+    pub family: Families,
+    pub rank: u8,
+    pub atk: u16,
+    pub hp: u16,
+    pub trigger: Triggers,
+    pub ability: Abilities,
+    #[serde(default)]
+    pub keywords: Keywords,
+    #[serde(default)]
+    pub description: String,
+    /// Path of the card art, relative to `assets/`
+    pub art: String,
+}
+
+/// Definitions of all the cards, one for each `BaseCards` variant.
+#[derive(Debug, Clone)]
+pub struct CardRegistry {
+    /// Ordered like `BaseCards::ALL`
+    definitions: Vec<CardDefinition>,
+}
+
+/// Card definitions built into the game, used until `CardRegistry::install` is called.
+pub const DEFAULT_CARDS: &str = include_str!("../../assets/cards.ron");
+
+static REGISTRY: OnceLock<CardRegistry> = OnceLock::new();
+
+impl CardRegistry {
+    /// Parses a list of card definitions, e.g. `assets/cards.ron`.
+    pub fn from_ron(ron: &str) -> Result<Self, String> {
+        let definitions: Vec<CardDefinition> = ron::from_str(ron).map_err(|e| e.to_string())?;
+        let mut ordered = Vec::with_capacity(BaseCards::ALL.len());
+        for base_card in BaseCards::ALL.iter() {
+            let mut matching = definitions.iter().filter(|definition| definition.id == *base_card);
+            match (matching.next(), matching.next()) {
+                (Some(definition), None) if !(1..=MAX_RANK).contains(&definition.rank) =>
+                    return Err(format!("{:?} has rank {}, ranks go from 1 to {}", base_card, definition.rank, MAX_RANK)),
+                (Some(definition), None) => ordered.push(definition.clone()),
+                (None, _) => return Err(format!("{:?} is not defined", base_card)),
+                (Some(_), Some(_)) => return Err(format!("{:?} is defined twice", base_card)),
+            }
+        }
+        Ok(CardRegistry { definitions: ordered })
+    }
+
+    /// Makes these definitions the ones of `BaseCards`, before any card is used.
+    /// Fails if the registry is already set.
+    pub fn install(self) -> Result<(), CardRegistry> {
+        REGISTRY.set(self)
+    }
+
+    pub fn get(&self, base_card: BaseCards) -> &CardDefinition {
+        &self.definitions[base_card as usize]
+    }
+
+    pub fn definitions(&self) -> &[CardDefinition] {
+        &self.definitions
+    }
+}
+
+/// The installed registry, or the default cards.
+pub fn registry() -> &'static CardRegistry {
+    REGISTRY.get_or_init(|| CardRegistry::from_ron(DEFAULT_CARDS).expect("Invalid default cards"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_cards_define_every_card() {
+        let registry = CardRegistry::from_ron(DEFAULT_CARDS).unwrap();
+        for (definition, base_card) in registry.definitions().iter().zip(BaseCards::ALL.iter()) {
+            assert_eq!(definition.id, *base_card);
+        }
+    }
+
+    #[test]
+    fn every_card_is_parsed_from_its_name() {
+        for &base_card in BaseCards::ALL.iter() {
+            assert_eq!(format!("{:?}", base_card).parse(), Ok(base_card));
+        }
+        assert_eq!("Mush9".parse::<BaseCards>(), Err("Unknown card Mush9".to_string()));
+    }
+
+    #[test]
+    fn abilities_are_resolved_in_one_place() {
+        for ability in Abilities::COMBAT.iter() {
+            assert!(!Abilities::SHOP.contains(ability), "{:?}", ability);
+        }
+        assert!(!Abilities::None.implemented());
+    }
+
+    #[test]
+    fn merged_cards_keep_their_gains() {
+        let mut copies = [Card::new(BaseCards::Mush2, 0), Card::new(BaseCards::Mush2, 1), Card::new(BaseCards::Mush2, 2)];
+        copies[1].hp += 3;
+        copies[2].atk += 1;
+        let golden = Card::merge(&copies, 3);
+        assert!(golden.golden);
+        assert_eq!((golden.id, golden.atk, golden.hp), (3, 3, 5));
+    }
+
+    #[test]
+    fn every_card_must_be_defined_once() {
+        let definitions: Vec<CardDefinition> = ron::from_str(DEFAULT_CARDS).unwrap();
+
+        let missing = ron::to_string(&definitions[1..].to_vec()).unwrap();
+        assert_eq!(CardRegistry::from_ron(&missing).unwrap_err(), "Mush1 is not defined");
+
+        let mut twice = definitions.clone();
+        twice.push(definitions[0].clone());
+        assert_eq!(CardRegistry::from_ron(&ron::to_string(&twice).unwrap()).unwrap_err(), "Mush1 is defined twice");
+    }
+
+    #[test]
+    fn ranks_are_bounded() {
+        let mut definitions: Vec<CardDefinition> = ron::from_str(DEFAULT_CARDS).unwrap();
+        definitions[0].rank = MAX_RANK + 1;
+        assert_eq!(CardRegistry::from_ron(&ron::to_string(&definitions).unwrap()).unwrap_err(), "Mush1 has rank 7, ranks go from 1 to 6");
+    }
+}
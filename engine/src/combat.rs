@@ -0,0 +1,1082 @@
+use std::collections::VecDeque;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use derive_more::Display;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::card::{Abilities, BaseCards, Card, Triggers};
+
+/* Notations:
+    - att: attacking player
+    - def: attacked player
+    - hb: half-board of a player
+    - id: uid of a card / player
+    - index: index of a card inside a player's hand
+ */
+
+/// What the simulator needs to know about a player.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HalfBoard {
+    pub id: u16,
+    pub hp: u16,
+    pub extra_coins: u16,
+    #[serde(default)]
+    pub shop_level: u16,
+    pub board: Vec<Card>,
+}
+
+/// Events of a fight, its outcome, and both players once it is over.
+pub struct CombatResult {
+    pub events: Vec<CombatEvents>,
+    pub outcome: CombatOutcome,
+    pub hb1: HalfBoard,
+    pub hb2: HalfBoard,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CombatOutcome {
+    /// Id of the player whose board survived
+    Winner(u16),
+    /// Both boards were emptied, or neither could be after `MAX_ATTACKS` attacks: nobody takes damage
+    Draw,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CombatEvents {
+    Attack { att_id: u16, att_card_index: u8, def_card_index: u8 },
+    EndOfAttack { att_id: u16, att_card_index: u8, def_card_index: u8 },
+    AllyAttack { player_id: u16, att_card_index: u8, def_card_index: u8 },
+    EndOfAllyAttack { player_id: u16, att_card_index: u8, def_card_index: u8 },
+    Death { player_id: u16, card_id: u32 },
+    /// A `permanent` change is kept on the card after the fight
+    StatsChange { player_id: u16, card_id: u32, hp: i32, at: i32, permanent: bool },
+    ApplyAbility { card_index: u8, player_id: u16, ability: Abilities, card_id: u32 },
+    GoldChange { player_id: u16, change: i32 },
+    Summon { player_id: u16, card_index: u8, card: Card },
+    /// The shield of the card absorbed a hit
+    ShieldBroken { player_id: u16, card_id: u32 },
+    PlayersAttack { att_id: u16, change_def_hp: i32 },
+    /// Followed by the effects of `Triggers::StartOfCombat` cards
+    StartOfCombat,
+    /// Followed by the effects of `Triggers::EndOfCombat` cards
+    EndOfCombat,
+    /// `MAX_RESOLVED_TRIGGERS` abilities were resolved in a row, the `dropped` ones still queued are ignored
+    TriggerLimit { dropped: u16 },
+}
+
+impl Display for CombatEvents {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CombatEvents::GoldChange { player_id, change } => write!(f, "GoldChange to={} of={} gold", player_id, change),
+            CombatEvents::Attack { att_id, att_card_index: att_card_id, def_card_index: def_card_id } => { write!(f, "Attack of {}.{} on {}.{}", att_id, att_card_id, 1 - att_id, def_card_id) }
+            CombatEvents::Death { player_id, card_id } => { write!(f, "Death of {}.{}", player_id, card_id) }
+            CombatEvents::StatsChange { player_id, card_id, hp, at, permanent } => { write!(f, "Stats Change of {}.{}: {:+} ATK {:+} HP{}", player_id, card_id, at, hp, if *permanent { " (permanent)" } else { "" }) }
+            CombatEvents::ApplyAbility { card_index, player_id, ability, card_id: _ } => { write!(f, "Effect {} of card {}.{}", ability, player_id, card_index) }
+            CombatEvents::PlayersAttack { att_id, change_def_hp } => { write!(f, "Player {} takes {} to their opponent", att_id, change_def_hp) }
+            CombatEvents::EndOfAttack { .. } => { write!(f, "End of attack") }
+            CombatEvents::AllyAttack { player_id, att_card_index, def_card_index } => { write!(f, "Attack of {}.{} on its ally {}.{}", player_id, att_card_index, player_id, def_card_index) }
+            CombatEvents::EndOfAllyAttack { .. } => { write!(f, "End of ally attack") }
+            CombatEvents::Summon { player_id, card_index, card } => { write!(f, "Summon of {} at {}.{}", card.base_card.name(), player_id, card_index) }
+            CombatEvents::ShieldBroken { player_id, card_id } => { write!(f, "Shield of {}.{} broken", player_id, card_id) }
+            CombatEvents::StartOfCombat => { write!(f, "Start of combat") }
+            CombatEvents::EndOfCombat => { write!(f, "End of combat") }
+            CombatEvents::TriggerLimit { dropped } => { write!(f, "Trigger limit reached, {} abilities dropped", dropped) }
+        }
+    }
+}
+
+/// Variants of the combat rules, to compare them with the simulator.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CombatRules {
+    pub attack_order: AttackOrder,
+    pub damage: DamageFormula,
+    /// Turn of the run when the fight happens, for `DamageFormula::Turn`
+    #[serde(default)]
+    pub turn: u16,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AttackOrder {
+    /// The card that attacked the least attacks, the leftmost one first.
+    /// The first player is drawn at random.
+    #[default]
+    LeastPlayed,
+    /// Cards attack strictly from left to right, then start over from the leftmost one.
+    /// The player with more cards starts, ties are drawn at random.
+    LeftToRight,
+}
+
+/// Damage dealt to the loser of a fight by the winner, capped at the loser's HP.
+#[derive(Debug, Default, Display, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DamageFormula {
+    #[default]
+    #[display(fmt = "sum of the ranks of the surviving cards")]
+    RankSum,
+    #[display(fmt = "sum of the ranks of the surviving cards + shop level")]
+    RankSumPlusShopLevel,
+    #[display(fmt = "{} per lost fight", _0)]
+    Flat(u16),
+    #[display(fmt = "turn number")]
+    Turn,
+}
+
+impl DamageFormula {
+    pub fn damage(&self, winner: &HalfBoard, turn: u16) -> u16 {
+        let rank_sum = winner.board.iter().map(|card| card.base_card.rank() as u16).sum::<u16>();
+        match self {
+            DamageFormula::RankSum => rank_sum,
+            DamageFormula::RankSumPlusShopLevel => rank_sum + winner.shop_level,
+            DamageFormula::Flat(damage) => *damage,
+            DamageFormula::Turn => turn,
+        }
+    }
+}
+
+/// Parses `rank-sum`, `rank-sum-shop-level`, `turn` or a flat amount of damage.
+impl FromStr for DamageFormula {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rank-sum" => Ok(DamageFormula::RankSum),
+            "rank-sum-shop-level" => Ok(DamageFormula::RankSumPlusShopLevel),
+            "turn" => Ok(DamageFormula::Turn),
+            flat => flat.parse().map(DamageFormula::Flat).map_err(|_| format!("Invalid damage formula {}", flat)),
+        }
+    }
+}
+
+/// Number of slots on a board
+pub const BOARD_SIZE: usize = 7;
+
+/// Attacks of a fight at most, in case a fight changes the boards forever.
+/// Fights where nothing changes end after a round, see `changes_boards`.
+pub const MAX_ATTACKS: usize = 1000;
+
+fn get_number_of_cards(b: &HalfBoard) -> u8 {
+    b.board.len() as u8
+}
+
+/// Abilities aimed at an opponent card do nothing without `opponent_card_index`.
+fn apply_effect<T: Rng>(card_index: u8, opponent_card_index: Option<u8>, player_hb: &mut HalfBoard, opponent_hb: &mut HalfBoard, next_card_id: &mut u32, rng: &mut T) -> Vec<CombatEvents> {
+    let player_card = player_hb.board[card_index as usize];
+    let opponent_card = opponent_card_index.map(|index| (index as usize, opponent_hb.board[index as usize]));
+    let player_id = player_hb.id;
+    let opponent_id = opponent_hb.id;
+    let ability = player_card.base_card.ability();
+    let magnitude = player_card.magnitude();
+    let mut events = vec![
+        CombatEvents::ApplyAbility { card_index, player_id, ability, card_id: player_card.id }
+    ];
+
+    match ability {
+        Abilities::ToxicSpores => {
+            if let Some((opponent_card_index, opponent_card)) = opponent_card {
+                opponent_hb.board[opponent_card_index].hp = 0;
+                events.push(CombatEvents::StatsChange { player_id: opponent_id, card_id: opponent_card.id, at: 0, hp: -(opponent_card.hp as i32), permanent: false });
+            }
+        }
+        Abilities::Gigantism => {
+            player_hb.board[card_index as usize].atk += magnitude;
+            events.push(CombatEvents::StatsChange { player_id, card_id: player_card.id, hp: 0, at: magnitude as i32, permanent: true })
+        }
+        Abilities::Sadism => {}, // Resolved once the attacker is back, see simulate_ally_attack
+        Abilities::ExplodingArmour => {
+            for card in &mut opponent_hb.board {
+                events.push(CombatEvents::StatsChange { player_id: opponent_id, hp: -(magnitude as i32), at: 0, card_id: card.id, permanent: false });
+                card.hp = relu(card.hp as i32 - magnitude as i32);
+            }
+        }
+        Abilities::Pillage => events.push(CombatEvents::GoldChange { change: magnitude as i32, player_id }),
+        Abilities::Trap => {
+            if let Some((opponent_card_index, opponent_card)) = opponent_card {
+                // Halved once, or twice for a golden card
+                let atk = opponent_card.atk >> magnitude;
+                let change_atk = opponent_card.atk as i32 - atk as i32;
+                opponent_hb.board[opponent_card_index].atk = atk;
+                events.push(CombatEvents::StatsChange { player_id: opponent_id, hp: 0, at: -change_atk, card_id: opponent_card.id, permanent: false })
+            }
+        }
+        Abilities::Multiplication => {
+            // Dead cards are still on the board, they leave room for the spiders
+            let alive = player_hb.board.iter().filter(|card| card.hp > 0).count();
+            // Golden eggs also hatch spiders of their own rank
+            let rank = player_card.base_card.rank() + magnitude as u8 - 1;
+            for i in 0..BOARD_SIZE.saturating_sub(alive) {
+                if let Some(base_card) = BaseCards::random_spider_below(rank, rng) {
+                    let card = Card::new(base_card, *next_card_id);
+                    *next_card_id += 1;
+                    let index = card_index as usize + 1 + i;
+                    player_hb.board.insert(index, card);
+                    events.push(CombatEvents::Summon { player_id, card_index: index as u8, card });
+                }
+            }
+        }
+        Abilities::Glitch => {
+            if let Some((opponent_card_index, opponent_card)) = opponent_card {
+                let change = 2 * magnitude as i32;
+                if rng.gen() {
+                    opponent_hb.board[opponent_card_index].atk = relu(opponent_card.atk as i32 - change);
+                    events.push(CombatEvents::StatsChange { player_id: opponent_id, card_id: opponent_card.id, hp: 0, at: -change, permanent: true });
+                } else {
+                    opponent_hb.board[opponent_card_index].hp = relu(opponent_card.hp as i32 - change);
+                    events.push(CombatEvents::StatsChange { player_id: opponent_id, at: 0, hp: -change, card_id: opponent_card.id, permanent: true });
+                }
+            }
+        }
+        _ => {}
+    };
+
+    events
+}
+
+#[inline]
+fn relu(i: i32) -> u16 {
+    if i < 0 { 0 } else { i as u16 }
+}
+
+#[inline]
+fn min2<T: PartialOrd>(x: T, y: T) -> T { if x < y { x } else { y } }
+
+/// Index of a random card of `hb`, among the cards with Taunt if there are some.
+fn random_target<T: Rng>(hb: &HalfBoard, rng: &mut T) -> u8 {
+    let taunts: Vec<usize> = hb.board.iter().enumerate()
+        .filter(|(_, card)| card.keywords.taunt)
+        .map(|(i, _)| i)
+        .collect();
+    if taunts.is_empty() {
+        rng.gen_range(0..get_number_of_cards(hb))
+    } else {
+        taunts[rng.gen_range(0..taunts.len())] as u8
+    }
+}
+
+/// `attacker` hits the card of `hb` at `index`: a shield absorbs the hit, venom kills if it deals damage.
+fn hit(attacker: Card, index: usize, hb: &mut HalfBoard, events: &mut Vec<CombatEvents>) {
+    let defender = &mut hb.board[index];
+    if defender.keywords.shield {
+        defender.keywords.shield = false;
+        events.push(CombatEvents::ShieldBroken { player_id: hb.id, card_id: defender.id });
+        return;
+    }
+    let damage = if attacker.keywords.venom && attacker.atk > 0 { attacker.atk.max(defender.hp) } else { attacker.atk };
+    defender.hp = relu(defender.hp as i32 - damage as i32);
+    events.push(CombatEvents::StatsChange { player_id: hb.id, card_id: defender.id, at: 0, hp: -(damage as i32), permanent: false });
+}
+
+fn simulate_attack<T: Rng>(att_card_index: usize, att_hb: &mut HalfBoard, def_hb: &mut HalfBoard, next_card_id: &mut u32, rng: &mut T) -> (Vec<CombatEvents>, Option<usize>) {
+    let def_card_index = random_target(def_hb, rng);
+    let mut events = Vec::with_capacity(2);
+
+    let att_card = att_hb.board[att_card_index];
+    let def_card = def_hb.board[def_card_index as usize];
+
+    let att_card_index = att_card_index as u8;
+
+    events.push(CombatEvents::Attack { att_card_index, att_id: att_hb.id, def_card_index });
+
+    hit(att_card, def_card_index as usize, def_hb, &mut events);
+    hit(def_card, att_card_index as usize, att_hb, &mut events);
+
+    // Triggers (checked against the stats after the exchange of blows)
+    let att_card_trigger = att_card.base_card.trigger();
+    let def_card_trigger = def_card.base_card.trigger();
+    let att_hp = att_hb.board[att_card_index as usize].hp;
+    let def_hp = def_hb.board[def_card_index as usize].hp;
+    let mut queue = VecDeque::new();
+    if att_card_trigger == Triggers::Hit
+        || (att_card_trigger == Triggers::Kill && def_hp == 0)
+        || (att_card_trigger == Triggers::Survived && att_hp > 0)
+        || (att_card_trigger == Triggers::Death && att_hp == 0)
+    {
+        queue.push_back(PendingTrigger { player_id: att_hb.id, card_id: att_card.id, target_id: Some(def_card.id) });
+    }
+    if def_card_trigger == Triggers::Hit
+        || (def_card_trigger == Triggers::Kill && att_hp == 0)
+        || (def_card_trigger == Triggers::Death && def_hp == 0)
+    {
+        queue.push_back(PendingTrigger { player_id: def_hb.id, card_id: def_card.id, target_id: Some(att_card.id) });
+    }
+    events.append(&mut resolve_triggers(queue, att_hb, def_hb, next_card_id, rng));
+
+    remove_dead_cards(att_hb, &mut events);
+    remove_dead_cards(def_hb, &mut events);
+
+    let mut survived = false;
+    let mut att_card_index = att_card_index;
+    for (i, &card) in att_hb.board.iter().enumerate() {
+        if card.id == att_card.id {
+            survived = true;
+            att_card_index = i as u8;
+        }
+    }
+
+    if survived {
+        events.push(CombatEvents::EndOfAttack {att_card_index, att_id: att_hb.id, def_card_index})
+    }
+
+    if survived && att_card.base_card.ability() == Abilities::Sadism && rng.gen() {
+        events.append(&mut simulate_ally_attack(att_card_index, att_hb, def_hb, next_card_id, rng));
+    }
+
+    // Index of the attacker if it attacks again
+    let replay = if survived && att_card_trigger == Triggers::Survived && att_card.base_card.ability() == Abilities::Dexterity
+        && !def_hb.board.is_empty() {
+        Some(att_card_index as usize)
+    } else {
+        None
+    };
+
+    (events, replay)
+}
+
+/// The attacker hits a random ally, both cards take damage.
+/// Cards dying there trigger their Death abilities and the Kill ability of their ally, as in an attack.
+fn simulate_ally_attack<T: Rng>(att_card_index: u8, hb: &mut HalfBoard, opponent_hb: &mut HalfBoard, next_card_id: &mut u32, rng: &mut T) -> Vec<CombatEvents> {
+    let mut events = vec![];
+    if get_number_of_cards(hb) < 2 {
+        return events;
+    }
+
+    // Any card but the attacker
+    let mut def_card_index = rng.gen_range(0..get_number_of_cards(hb) - 1);
+    if def_card_index >= att_card_index {
+        def_card_index += 1;
+    }
+
+    let att_card = hb.board[att_card_index as usize];
+    let def_card = hb.board[def_card_index as usize];
+
+    events.push(CombatEvents::AllyAttack { player_id: hb.id, att_card_index, def_card_index });
+
+    hit(att_card, def_card_index as usize, hb, &mut events);
+    hit(def_card, att_card_index as usize, hb, &mut events);
+
+    // The killer being an ally, abilities aimed at an opponent card target a random one
+    let att_hp = hb.board[att_card_index as usize].hp;
+    let def_hp = hb.board[def_card_index as usize].hp;
+    let mut queue = VecDeque::new();
+    for &(card, hp, other_hp) in [(att_card, att_hp, def_hp), (def_card, def_hp, att_hp)].iter() {
+        let trigger = card.base_card.trigger();
+        if (trigger == Triggers::Death && hp == 0) || (trigger == Triggers::Kill && other_hp == 0) {
+            let target_id = if opponent_hb.board.is_empty() {
+                None
+            } else {
+                Some(opponent_hb.board[random_target(opponent_hb, rng) as usize].id)
+            };
+            queue.push_back(PendingTrigger { player_id: hb.id, card_id: card.id, target_id });
+        }
+    }
+    events.append(&mut resolve_triggers(queue, hb, opponent_hb, next_card_id, rng));
+
+    remove_dead_cards(hb, &mut events);
+    remove_dead_cards(opponent_hb, &mut events);
+
+    // The attacker moves left if the ally was on its left and died
+    if let Some(att_card_index) = hb.board.iter().position(|card| card.id == att_card.id) {
+        events.push(CombatEvents::EndOfAllyAttack { player_id: hb.id, att_card_index: att_card_index as u8, def_card_index });
+    }
+
+    events
+}
+
+/// Whether the event changes a card, adds one or removes one.
+/// A round of attacks without such events would repeat forever.
+fn changes_boards(event: &CombatEvents) -> bool {
+    match *event {
+        CombatEvents::StatsChange { hp, at, .. } => hp != 0 || at != 0,
+        CombatEvents::ShieldBroken { .. } | CombatEvents::Death { .. } | CombatEvents::Summon { .. } => true,
+        _ => false,
+    }
+}
+
+fn remove_dead_cards(hb: &mut HalfBoard, events: &mut Vec<CombatEvents>) {
+    for &card in &hb.board {
+        if card.hp == 0 {
+            events.push(CombatEvents::Death { player_id: hb.id, card_id: card.id });
+        }
+    };
+    hb.board.retain(|card| card.hp > 0);
+}
+
+/// An ability waiting in the trigger queue.
+#[derive(Clone, Copy)]
+struct PendingTrigger {
+    player_id: u16,
+    card_id: u32,
+    /// Opponent card aimed at by the ability
+    target_id: Option<u32>,
+}
+
+/// Abilities resolved by a single queue at most, in case abilities keep triggering each other
+const MAX_RESOLVED_TRIGGERS: usize = 64;
+
+/// Resolves the queued abilities one after the other, first in, first out.
+/// Each ability can queue follow-up triggers, checked in board order, the ability owner's board first:
+/// - the Death trigger of each card it brought to 0 HP, aimed at the ability owner if it is an opponent,
+/// - the Kill trigger of the ability owner if it brought an opponent card to 0 HP.
+///
+/// Dead cards stay on the board until the queue is empty.
+fn resolve_triggers<T: Rng>(mut queue: VecDeque<PendingTrigger>, hb1: &mut HalfBoard, hb2: &mut HalfBoard, next_card_id: &mut u32, rng: &mut T) -> Vec<CombatEvents> {
+    let mut events = vec![];
+    let mut resolved = 0;
+
+    while let Some(PendingTrigger { player_id, card_id, target_id }) = queue.pop_front() {
+        if resolved == MAX_RESOLVED_TRIGGERS {
+            // This trigger and the ones after it
+            events.push(CombatEvents::TriggerLimit { dropped: queue.len() as u16 + 1 });
+            break;
+        }
+        resolved += 1;
+
+        let (player_hb, opponent_hb) = if hb1.id == player_id { (&mut *hb1, &mut *hb2) } else { (&mut *hb2, &mut *hb1) };
+        let card_index = match player_hb.board.iter().position(|card| card.id == card_id) {
+            Some(card_index) => card_index,
+            None => continue,
+        };
+        let opponent_card_index = target_id.and_then(|target_id| opponent_hb.board.iter().position(|card| card.id == target_id));
+
+        let alive: Vec<u32> = player_hb.board.iter().chain(opponent_hb.board.iter())
+            .filter(|card| card.hp > 0)
+            .map(|card| card.id)
+            .collect();
+        let trigger = player_hb.board[card_index].base_card.trigger();
+        events.append(&mut apply_effect(card_index as u8, opponent_card_index.map(|index| index as u8), player_hb, opponent_hb, next_card_id, rng));
+
+        let mut killed_opponent = false;
+        for &(hb, is_owner) in [(&*player_hb, true), (&*opponent_hb, false)].iter() {
+            for card in hb.board.iter().filter(|card| card.hp == 0 && alive.contains(&card.id)) {
+                if card.base_card.trigger() == Triggers::Death {
+                    let target_id = if is_owner { None } else { Some(card_id) };
+                    queue.push_back(PendingTrigger { player_id: hb.id, card_id: card.id, target_id });
+                }
+                killed_opponent |= !is_owner;
+            }
+        }
+        if killed_opponent && trigger == Triggers::Kill {
+            queue.push_back(PendingTrigger { player_id, card_id, target_id: None });
+        }
+    }
+
+    events
+}
+
+/// Resolves the abilities of the `triggered` cards of a phase, before the first attack or after the last one.
+/// Cards take turns between both boards from left to right, starting with the first card of `first_hb`.
+/// Only cards on the board when the phase starts are triggered, if they are still alive.
+/// Abilities aimed at an opponent card target a random one, honouring Taunt.
+fn simulate_phase<T: Rng>(triggered: impl Fn(&Card) -> bool, first_hb: &mut HalfBoard, second_hb: &mut HalfBoard, next_card_id: &mut u32, rng: &mut T) -> Vec<CombatEvents> {
+    let mut events = vec![];
+
+    let triggered_cards = |hb: &HalfBoard| -> Vec<(u16, u32)> {
+        hb.board.iter().filter(|card| triggered(card)).map(|card| (hb.id, card.id)).collect()
+    };
+    let first_cards = triggered_cards(first_hb);
+    let second_cards = triggered_cards(second_hb);
+    let mut order = vec![];
+    for i in 0..first_cards.len().max(second_cards.len()) {
+        order.extend(first_cards.get(i));
+        order.extend(second_cards.get(i));
+    }
+
+    for (player_id, card_id) in order {
+        let (player_hb, opponent_hb) = if first_hb.id == player_id { (&mut *first_hb, &mut *second_hb) } else { (&mut *second_hb, &mut *first_hb) };
+        if player_hb.board.iter().any(|card| card.id == card_id) {
+            let target_id = if opponent_hb.board.is_empty() {
+                None
+            } else {
+                Some(opponent_hb.board[random_target(opponent_hb, rng) as usize].id)
+            };
+            let queue = VecDeque::from(vec![PendingTrigger { player_id, card_id, target_id }]);
+            events.append(&mut resolve_triggers(queue, player_hb, opponent_hb, next_card_id, rng));
+            remove_dead_cards(player_hb, &mut events);
+            remove_dead_cards(opponent_hb, &mut events);
+        }
+    }
+
+    events
+}
+
+/// Applies what lasts after the fight: permanent stats changes, gold and player damage.
+/// Cards killed in combat come back, unless their permanent HP dropped to 0.
+fn after_combat(mut hb: HalfBoard, events: &[CombatEvents]) -> HalfBoard {
+    for event in events {
+        match *event {
+            CombatEvents::StatsChange { player_id, card_id, hp, at, permanent: true } if player_id == hb.id => {
+                for card in hb.board.iter_mut().filter(|card| card.id == card_id) {
+                    card.hp = relu(card.hp as i32 + hp);
+                    card.atk = relu(card.atk as i32 + at);
+                }
+            }
+            CombatEvents::GoldChange { player_id, change } if player_id == hb.id => {
+                hb.extra_coins = relu(hb.extra_coins as i32 + change);
+            }
+            CombatEvents::PlayersAttack { att_id, change_def_hp } if att_id != hb.id => {
+                hb.hp = relu(hb.hp as i32 + change_def_hp);
+            }
+            _ => {}
+        }
+    }
+    hb.board.retain(|card| card.hp > 0);
+    hb
+}
+
+pub fn simulate_combat<T: Rng>(mut hb1: HalfBoard, mut hb2: HalfBoard, rules: &CombatRules, rng: &mut T) -> CombatResult {
+    let start = (hb1.clone(), hb2.clone());
+    let mut events = Vec::new();
+
+    // Summoned cards get ids unused by both boards
+    let mut next_card_id = hb1.board.iter().chain(hb2.board.iter()).map(|card| card.id + 1).max().unwrap_or(0);
+
+    let mut to_play = match rules.attack_order {
+        AttackOrder::LeftToRight if hb1.board.len() != hb2.board.len() => hb1.board.len() > hb2.board.len(),
+        _ => rng.gen::<bool>(),
+    };
+    let first_to_play = to_play;
+    // Cards of each player that already attacked in the current round, for AttackOrder::LeftToRight.
+    // Ids rather than indexes, since cards die and are summoned during the opponent's turn.
+    let mut played = (vec![], vec![]);
+
+    events.push(CombatEvents::StartOfCombat);
+    let (first_hb, second_hb) = if first_to_play { (&mut hb1, &mut hb2) } else { (&mut hb2, &mut hb1) };
+    events.extend(simulate_phase(|card| card.base_card.trigger() == Triggers::StartOfCombat, first_hb, second_hb, &mut next_card_id, rng));
+
+    // While each player has something to play
+    let mut attacks = 0;
+    // Attacks in a row that changed nothing
+    let mut idle_attacks = 0;
+    while !hb1.board.is_empty() && !hb2.board.is_empty() && attacks < MAX_ATTACKS {
+        // Every card attacked once since the boards last changed
+        if idle_attacks >= 2 * hb1.board.len().max(hb2.board.len()) {
+            break;
+        }
+        attacks += 1;
+        let first_event = events.len();
+        let (player_hb, opponent_hb, played) = if to_play {
+            (&mut hb1, &mut hb2, &mut played.0)
+        } else {
+            (&mut hb2, &mut hb1, &mut played.1)
+        };
+
+        let next_card_to_play = match rules.attack_order {
+            AttackOrder::LeastPlayed => player_hb.board.iter().enumerate().map(|(i, card)| (card.played, i)).min().unwrap().1,
+            // The card right of the last survivor that played, summoned cards included
+            AttackOrder::LeftToRight => match player_hb.board.iter().rposition(|card| played.contains(&card.id)) {
+                Some(i) if i + 1 < player_hb.board.len() => i + 1,
+                _ => 0,
+            },
+        };
+        player_hb.board[next_card_to_play].played = player_hb.board[next_card_to_play].played.saturating_add(1);
+        // The attacker and the cards on its left
+        *played = player_hb.board[..=next_card_to_play].iter().map(|card| card.id).collect();
+
+        let (new_events, replay) = simulate_attack(next_card_to_play, player_hb, opponent_hb, &mut next_card_id, rng);
+        events.extend(new_events);
+
+        if let Some(replay_index) = replay {
+            let (new_events, _) = simulate_attack(replay_index, player_hb, opponent_hb, &mut next_card_id, rng);
+            events.extend(new_events);
+        }
+
+        idle_attacks = if events[first_event..].iter().any(changes_boards) { 0 } else { idle_attacks + 1 };
+        to_play = !to_play;
+    }
+
+    events.push(CombatEvents::EndOfCombat);
+    let (first_hb, second_hb) = if first_to_play { (&mut hb1, &mut hb2) } else { (&mut hb2, &mut hb1) };
+    events.extend(simulate_phase(|card| card.base_card.trigger() == Triggers::EndOfCombat, first_hb, second_hb, &mut next_card_id, rng));
+
+    // The same attack can empty both boards, and a stalemate leaves both standing
+    let outcome = if hb1.board.is_empty() == hb2.board.is_empty() {
+        CombatOutcome::Draw
+    } else {
+        let (winner, loser) = if hb1.board.is_empty() { (hb2, hb1) } else { (hb1, hb2) };
+        let change_def_hp = -min2(loser.hp as i32, rules.damage.damage(&winner, rules.turn) as i32);
+        events.push(CombatEvents::PlayersAttack {
+            att_id: winner.id,
+            change_def_hp,
+        });
+        CombatOutcome::Winner(winner.id)
+    };
+
+    CombatResult {
+        hb1: after_combat(start.0, &events),
+        hb2: after_combat(start.1, &events),
+        events,
+        outcome,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use crate::card::{Families, Keywords};
+
+    use super::*;
+
+    /// Player `id` with `cards`, whose ids start at `100 * id`
+    fn half_board(id: u16, cards: &[BaseCards]) -> HalfBoard {
+        HalfBoard {
+            id,
+            hp: 20,
+            extra_coins: 0,
+            shop_level: 1,
+            board: cards.iter().enumerate().map(|(i, &card)| Card::new(card, 100 * id as u32 + i as u32)).collect(),
+        }
+    }
+
+    fn rng() -> StdRng {
+        StdRng::seed_from_u64(0)
+    }
+
+    fn abilities(events: &[CombatEvents]) -> Vec<(u16, Abilities)> {
+        events.iter().filter_map(|event| match *event {
+            CombatEvents::ApplyAbility { player_id, ability, .. } => Some((player_id, ability)),
+            _ => None,
+        }).collect()
+    }
+
+    fn effect(card: BaseCards, opponent: &[BaseCards], target: Option<u8>) -> (Vec<CombatEvents>, HalfBoard, HalfBoard) {
+        let mut player_hb = half_board(0, &[card]);
+        let mut opponent_hb = half_board(1, opponent);
+        let mut next_card_id = 1000;
+        let events = apply_effect(0, target, &mut player_hb, &mut opponent_hb, &mut next_card_id, &mut rng());
+        (events, player_hb, opponent_hb)
+    }
+
+    fn attack(attacker: BaseCards, defenders: &[BaseCards]) -> (Vec<CombatEvents>, Option<usize>, HalfBoard, HalfBoard) {
+        let mut att_hb = half_board(0, &[attacker]);
+        let mut def_hb = half_board(1, defenders);
+        let mut next_card_id = 1000;
+        let (events, replay) = simulate_attack(0, &mut att_hb, &mut def_hb, &mut next_card_id, &mut rng());
+        (events, replay, att_hb, def_hb)
+    }
+
+    #[test]
+    fn ally_attacks_trigger_death_abilities() {
+        let mut hb1 = half_board(0, &[BaseCards::Merch2, BaseCards::Merch3]);
+        let mut hb2 = half_board(1, &[BaseCards::Mush2]);
+        let mut next_card_id = 200;
+        let events = simulate_ally_attack(0, &mut hb1, &mut hb2, &mut next_card_id, &mut StdRng::seed_from_u64(0));
+
+        // The Mandra killed by Estan explodes and kills the Gomphidius
+        assert!(events.contains(&CombatEvents::ApplyAbility { card_index: 1, player_id: 0, ability: Abilities::ExplodingArmour, card_id: 1 }));
+        assert!(events.contains(&CombatEvents::Death { player_id: 1, card_id: 100 }));
+        assert!(hb2.board.is_empty());
+        assert_eq!(hb1.board.iter().map(|card| card.id).collect::<Vec<_>>(), vec![0]);
+    }
+
+    /// Ids of the cards whose ability is applied in `events`
+    fn applied_ids(events: &[CombatEvents]) -> Vec<u32> {
+        events.iter().filter_map(|event| match *event {
+            CombatEvents::ApplyAbility { card_id, .. } => Some(card_id),
+            _ => None,
+        }).collect()
+    }
+
+    #[test]
+    fn phase_triggers_take_turns() {
+        // No card has a phase trigger yet, Titanicus stands for one: Gigantism kills nobody
+        let mut hb1 = half_board(0, &[BaseCards::Mush8, BaseCards::Mush1, BaseCards::Mush8]);
+        let mut hb2 = half_board(1, &[BaseCards::Mush8, BaseCards::Mush8, BaseCards::Mush8]);
+        let mut next_card_id = 200;
+        let events = simulate_phase(|card| card.base_card == BaseCards::Mush8, &mut hb2, &mut hb1, &mut next_card_id, &mut rng());
+        assert_eq!(applied_ids(&events), vec![100, 0, 101, 2, 102]);
+    }
+
+    #[test]
+    fn phase_skips_cards_killed_earlier() {
+        // Amanita kills the Titanicus before its turn
+        let mut hb1 = half_board(0, &[BaseCards::Mush5]);
+        let mut hb2 = half_board(1, &[BaseCards::Mush8, BaseCards::Mush8]);
+        hb2.board[0].keywords.taunt = true;
+        let mut next_card_id = 200;
+        let phase = |card: &Card| card.base_card == BaseCards::Mush5 || card.base_card == BaseCards::Mush8;
+        let events = simulate_phase(phase, &mut hb1, &mut hb2, &mut next_card_id, &mut rng());
+        assert_eq!(applied_ids(&events), vec![0, 101]);
+        assert!(events.contains(&CombatEvents::Death { player_id: 1, card_id: 100 }));
+        assert_eq!(hb2.board.iter().map(|card| card.id).collect::<Vec<_>>(), vec![101]);
+    }
+
+    #[test]
+    fn trigger_chains_are_bounded() {
+        // No card feeds a chain forever, so Gigantism is queued again and again as if it did
+        let mut hb1 = half_board(0, &[BaseCards::Mush8]);
+        let mut hb2 = half_board(1, &[BaseCards::Spid1]);
+        let trigger = PendingTrigger { player_id: 0, card_id: hb1.board[0].id, target_id: None };
+        let queue = std::iter::repeat_n(trigger, MAX_RESOLVED_TRIGGERS + 10).collect();
+        let mut next_card_id = 200;
+        let events = resolve_triggers(queue, &mut hb1, &mut hb2, &mut next_card_id, &mut StdRng::seed_from_u64(0));
+
+        let resolved = events.iter().filter(|e| matches!(e, CombatEvents::ApplyAbility { .. })).count();
+        assert_eq!(resolved, MAX_RESOLVED_TRIGGERS);
+        assert_eq!(events.last(), Some(&CombatEvents::TriggerLimit { dropped: 10 }));
+        assert_eq!(hb1.board[0].atk, Card::new(BaseCards::Mush8, 0).atk + MAX_RESOLVED_TRIGGERS as u16);
+    }
+
+    #[test]
+    fn damage_formulas_parse() {
+        assert_eq!("rank-sum".parse(), Ok(DamageFormula::RankSum));
+        assert_eq!("rank-sum-shop-level".parse(), Ok(DamageFormula::RankSumPlusShopLevel));
+        assert_eq!("turn".parse(), Ok(DamageFormula::Turn));
+        assert_eq!("3".parse(), Ok(DamageFormula::Flat(3)));
+        assert_eq!("three".parse::<DamageFormula>(), Err("Invalid damage formula three".to_string()));
+    }
+
+    #[test]
+    fn turn_damage_is_the_turn_number() {
+        let winner = half_board(0, &[BaseCards::Mush8]);
+        assert_eq!(DamageFormula::Turn.damage(&winner, 7), 7);
+        assert_eq!(DamageFormula::RankSum.damage(&winner, 7), winner.board[0].base_card.rank() as u16);
+    }
+
+    #[test]
+    fn gigantism_gives_one_atk() {
+        let (events, player_hb, _) = effect(BaseCards::Mush8, &[], None);
+        assert_eq!(player_hb.board[0].atk, 6);
+        assert_eq!(events[1], CombatEvents::StatsChange { player_id: 0, card_id: 0, hp: 0, at: 1, permanent: true });
+    }
+
+    #[test]
+    fn golden_abilities_are_stronger() {
+        let mut player_hb = half_board(0, &[]);
+        player_hb.board.push(Card::new_golden(BaseCards::Merch3, 0));
+        let mut opponent_hb = half_board(1, &[BaseCards::Mush1, BaseCards::Merch8]);
+        apply_effect(0, None, &mut player_hb, &mut opponent_hb, &mut 1000, &mut rng());
+        assert_eq!(opponent_hb.board.iter().map(|card| card.hp).collect::<Vec<_>>(), vec![1, 7]);
+
+        player_hb.board[0] = Card::new_golden(BaseCards::Spid3, 0);
+        apply_effect(0, Some(1), &mut player_hb, &mut opponent_hb, &mut 1000, &mut rng());
+        assert_eq!(opponent_hb.board[1].atk, 1);
+    }
+
+    #[test]
+    fn exploding_armour_hits_every_enemy() {
+        let (events, _, opponent_hb) = effect(BaseCards::Merch3, &[BaseCards::Mush1, BaseCards::Mush2], None);
+        assert_eq!(opponent_hb.board.iter().map(|card| card.hp).collect::<Vec<_>>(), vec![2, 0]);
+        assert_eq!(events.len(), 3);
+    }
+
+    #[test]
+    fn pillage_gives_one_gold() {
+        let (events, _, _) = effect(BaseCards::Merch5, &[BaseCards::Mush1], Some(0));
+        assert_eq!(events[1], CombatEvents::GoldChange { player_id: 0, change: 1 });
+    }
+
+    #[test]
+    fn trap_halves_the_target_atk() {
+        let (events, _, opponent_hb) = effect(BaseCards::Spid3, &[BaseCards::Merch8], Some(0));
+        assert_eq!(opponent_hb.board[0].atk, 2);
+        assert_eq!(events[1], CombatEvents::StatsChange { player_id: 1, card_id: 100, hp: 0, at: -3, permanent: false });
+
+        let (events, _, opponent_hb) = effect(BaseCards::Spid3, &[BaseCards::Merch8], None);
+        assert_eq!(opponent_hb.board[0].atk, 5);
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    fn multiplication_fills_the_board_with_lower_spiders() {
+        let mut player_hb = half_board(0, &[BaseCards::Mush1, BaseCards::Spid5]);
+        let mut opponent_hb = half_board(1, &[]);
+        let mut next_card_id = 1000;
+        let events = apply_effect(1, None, &mut player_hb, &mut opponent_hb, &mut next_card_id, &mut rng());
+
+        assert_eq!(player_hb.board.len(), BOARD_SIZE);
+        assert_eq!(next_card_id, 1005);
+        for (i, card) in player_hb.board.iter().enumerate().skip(2) {
+            assert!(card.base_card.family() == Families::Spiders);
+            assert!(card.base_card.rank() < BaseCards::Spid5.rank());
+            assert!(events.contains(&CombatEvents::Summon { player_id: 0, card_index: i as u8, card: *card }));
+        }
+    }
+
+    #[test]
+    fn glitch_removes_two_atk_or_two_hp() {
+        let mut seen = (false, false);
+        for seed in 0..32 {
+            let mut player_hb = half_board(0, &[BaseCards::Rob5]);
+            let mut opponent_hb = half_board(1, &[BaseCards::Merch8]);
+            let events = apply_effect(0, Some(0), &mut player_hb, &mut opponent_hb, &mut 1000, &mut StdRng::seed_from_u64(seed));
+            match (opponent_hb.board[0].atk, opponent_hb.board[0].hp) {
+                (3, 9) => seen.0 = true,
+                (5, 7) => seen.1 = true,
+                stats => panic!("Unexpected stats {:?}", stats),
+            }
+            assert!(matches!(events[1], CombatEvents::StatsChange { permanent: true, .. }));
+        }
+        assert_eq!(seen, (true, true));
+    }
+
+    #[test]
+    fn other_abilities_only_announce_themselves() {
+        // Sadism and Dexterity are resolved by `simulate_attack`, shop abilities by the game
+        let announced = BaseCards::ALL.iter()
+            .filter(|card| !Abilities::COMBAT.contains(&card.ability()) || [Abilities::Sadism, Abilities::Dexterity].contains(&card.ability()));
+        for &card in announced {
+            let (events, player_hb, opponent_hb) = effect(card, &[BaseCards::Merch8], Some(0));
+            assert_eq!(events.len(), 1, "{:?}", card);
+            assert_eq!(player_hb, half_board(0, &[card]));
+            assert_eq!(opponent_hb, half_board(1, &[BaseCards::Merch8]));
+        }
+    }
+
+    #[test]
+    fn combat_abilities_change_the_fight() {
+        let resolved = BaseCards::ALL.iter()
+            .filter(|card| Abilities::COMBAT.contains(&card.ability()) && ![Abilities::Sadism, Abilities::Dexterity].contains(&card.ability()));
+        for &card in resolved {
+            let (events, _, _) = effect(card, &[BaseCards::Merch8], Some(0));
+            assert!(events.len() > 1, "{:?}", card);
+        }
+    }
+
+    #[test]
+    fn hit_triggers_for_both_cards() {
+        let (events, _, att_hb, _) = attack(BaseCards::Spid3, &[BaseCards::Merch6]);
+        assert_eq!(abilities(&events), vec![(0, Abilities::Trap)]);
+        assert_eq!(att_hb.board[0].atk, 3);
+
+        let (events, _, att_hb, _) = attack(BaseCards::Merch6, &[BaseCards::Spid3]);
+        assert_eq!(abilities(&events), vec![(1, Abilities::Trap)]);
+        assert_eq!(att_hb.board[0].atk, 0);
+    }
+
+    #[test]
+    fn kill_triggers_only_on_a_kill() {
+        let (events, _, _, _) = attack(BaseCards::Mush8, &[BaseCards::Mush1]);
+        assert_eq!(abilities(&events), vec![(0, Abilities::Gigantism)]);
+
+        let (events, _, _, _) = attack(BaseCards::Mush8, &[BaseCards::Merch8]);
+        assert_eq!(abilities(&events), vec![]);
+
+        let (events, _, _, _) = attack(BaseCards::Mush1, &[BaseCards::Mush8]);
+        assert_eq!(abilities(&events), vec![(1, Abilities::Gigantism)]);
+    }
+
+    #[test]
+    fn death_triggers_for_both_cards() {
+        let (events, _, _, def_hb) = attack(BaseCards::Merch3, &[BaseCards::Mush1]);
+        assert_eq!(abilities(&events), vec![(0, Abilities::ExplodingArmour)]);
+        assert_eq!(def_hb.board[0].hp, 1);
+
+        let (events, _, att_hb, _) = attack(BaseCards::Mush1, &[BaseCards::Merch3]);
+        assert_eq!(abilities(&events), vec![(1, Abilities::ExplodingArmour)]);
+        assert_eq!(att_hb.board[0].hp, 1);
+    }
+
+    #[test]
+    fn survived_triggers_only_for_the_attacker() {
+        let (events, _, _, _) = attack(BaseCards::Merch8, &[BaseCards::Merch6]);
+        assert_eq!(abilities(&events), vec![(0, Abilities::Dexterity)]);
+
+        let (events, _, _, _) = attack(BaseCards::Merch6, &[BaseCards::Merch8]);
+        assert_eq!(abilities(&events), vec![]);
+
+        let (events, _, _, _) = attack(BaseCards::Merch8, &[BaseCards::Spid6]);
+        assert_eq!(abilities(&events), vec![]);
+    }
+
+    #[test]
+    fn dexterity_attacks_again_while_enemies_remain() {
+        let (_, replay, _, _) = attack(BaseCards::Merch8, &[BaseCards::Merch6, BaseCards::Merch6]);
+        assert_eq!(replay, Some(0));
+
+        // No enemy left
+        let (_, replay, _, _) = attack(BaseCards::Merch8, &[BaseCards::Mush1]);
+        assert_eq!(replay, None);
+
+        // Killed by venom
+        let (_, replay, _, _) = attack(BaseCards::Merch8, &[BaseCards::Spid6]);
+        assert_eq!(replay, None);
+    }
+
+    #[test]
+    fn venom_kills_only_when_it_deals_damage() {
+        let venomous = |atk| Card { atk, keywords: Keywords { venom: true, ..Keywords::default() }, ..Card::new(BaseCards::Spid6, 0) };
+        let mut hb = half_board(1, &[BaseCards::Merch8]);
+        let mut events = vec![];
+
+        hit(venomous(0), 0, &mut hb, &mut events);
+        assert_eq!(hb.board[0].hp, 9);
+
+        hb.board[0].keywords.shield = true;
+        hit(venomous(5), 0, &mut hb, &mut events);
+        assert_eq!(hb.board[0].hp, 9);
+
+        hit(venomous(1), 0, &mut hb, &mut events);
+        assert_eq!(hb.board[0].hp, 0);
+    }
+
+    #[test]
+    fn toxic_spores_kill_the_killer() {
+        let (events, _, att_hb, def_hb) = attack(BaseCards::Merch4, &[BaseCards::Mush5]);
+        assert_eq!(abilities(&events), vec![(1, Abilities::ToxicSpores)]);
+        assert!(def_hb.board.is_empty());
+        assert!(att_hb.board.is_empty());
+    }
+
+    #[test]
+    fn dexterity_replays_in_combat() {
+        let hb1 = half_board(0, &[BaseCards::Merch8]);
+        let hb2 = half_board(1, &[BaseCards::Merch6, BaseCards::Merch6]);
+        let result = simulate_combat(hb1, hb2, &CombatRules::default(), &mut rng());
+
+        let attackers: Vec<u16> = result.events.iter().filter_map(|event| match *event {
+            CombatEvents::Attack { att_id, .. } => Some(att_id),
+            _ => None,
+        }).collect();
+        let first = attackers.iter().position(|&att_id| att_id == 0).unwrap();
+        assert_eq!(attackers.get(first + 1), Some(&0));
+    }
+
+    /// Ids of the cards of `hb` attacking in `events`, in order
+    fn attacker_ids(hb: &HalfBoard, events: &[CombatEvents]) -> Vec<u32> {
+        let mut board: Vec<u32> = hb.board.iter().map(|card| card.id).collect();
+        let mut attackers = vec![];
+        for event in events {
+            match *event {
+                CombatEvents::Attack { att_id, att_card_index, .. } if att_id == hb.id => attackers.push(board[att_card_index as usize]),
+                CombatEvents::Death { player_id, card_id } if player_id == hb.id => board.retain(|&id| id != card_id),
+                CombatEvents::Summon { player_id, card_index, card } if player_id == hb.id => board.insert(card_index as usize, card.id),
+                _ => {}
+            }
+        }
+        attackers
+    }
+
+    #[test]
+    fn left_to_right_survives_deaths_on_the_left() {
+        let rules = CombatRules { attack_order: AttackOrder::LeftToRight, ..Default::default() };
+        let mut hb1 = half_board(0, &[BaseCards::Mush1, BaseCards::Mush1, BaseCards::Mush1]);
+        // The first attacker is hit back, then killed by the foe on its turn
+        hb1.board[0].hp = 2;
+        hb1.board[0].keywords.taunt = true;
+        hb1.board[1].hp = 10;
+        hb1.board[2].hp = 10;
+        let mut hb2 = half_board(1, &[BaseCards::Mush1]);
+        hb2.board[0].hp = 100;
+
+        for seed in 0..20 {
+            let result = simulate_combat(hb1.clone(), hb2.clone(), &rules, &mut StdRng::seed_from_u64(seed));
+            assert_eq!(attacker_ids(&hb1, &result.events)[..4], [0, 1, 2, 1], "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn idle_fights_stop_after_a_round() {
+        for &base_card in [BaseCards::Spid5, BaseCards::Mush5].iter() {
+            let hb1 = half_board(0, &[base_card, base_card]);
+            let hb2 = half_board(1, &[base_card]);
+            let result = simulate_combat(hb1, hb2, &CombatRules::default(), &mut rng());
+            let attacks = result.events.iter().filter(|event| matches!(event, CombatEvents::Attack { .. })).count();
+            assert_eq!(attacks, 4, "{:?}", base_card);
+            assert_eq!(result.outcome, CombatOutcome::Draw);
+        }
+    }
+
+    #[test]
+    fn stalemate_is_a_draw() {
+        let hb1 = half_board(0, &[BaseCards::Spid5]);
+        let hb2 = half_board(1, &[BaseCards::Spid5]);
+        let result = simulate_combat(hb1, hb2, &CombatRules::default(), &mut rng());
+        assert_eq!(result.outcome, CombatOutcome::Draw);
+        assert_eq!((result.hb1.hp, result.hb2.hp), (20, 20));
+    }
+
+    fn card_strategy() -> impl Strategy<Value = Card> {
+        (0..BaseCards::ALL.len(), 0..3u16, 0..3u16, any::<(bool, bool, bool)>())
+            .prop_map(|(i, atk, hp, (taunt, shield, venom))| {
+                let card = Card::new(BaseCards::ALL[i], 0);
+                Card { atk: card.atk + atk, hp: card.hp + hp, keywords: Keywords { taunt, shield, venom }, ..card }
+            })
+    }
+
+    fn half_board_strategy(id: u16) -> impl Strategy<Value = HalfBoard> {
+        (1..30u16, prop::collection::vec(card_strategy(), 0..=BOARD_SIZE))
+            .prop_map(move |(hp, cards)| HalfBoard {
+                id,
+                hp,
+                extra_coins: 0,
+                shop_level: 1,
+                board: cards.into_iter().enumerate().map(|(i, card)| Card { id: 100 * id as u32 + i as u32, ..card }).collect(),
+            })
+    }
+
+    /// Boards at the end of the fight, rebuilt from the events
+    fn follow_events(hb1: &HalfBoard, hb2: &HalfBoard, events: &[CombatEvents]) -> Result<(Vec<Card>, Vec<Card>), TestCaseError> {
+        let mut boards = [hb1.board.clone(), hb2.board.clone()];
+        let ids = [hb1.id, hb2.id];
+        let board = |player_id: u16| -> usize {
+            ids.iter().position(|&id| id == player_id).expect("Unknown player")
+        };
+        for event in events {
+            match *event {
+                CombatEvents::StatsChange { player_id, card_id, hp, at, .. } => {
+                    let i = board(player_id);
+                    let card = boards[i].iter_mut().find(|card| card.id == card_id);
+                    prop_assert!(card.is_some(), "{} changes a card off the board", event);
+                    let card = card.unwrap();
+                    card.hp = relu(card.hp as i32 + hp);
+                    card.atk = relu(card.atk as i32 + at);
+                }
+                CombatEvents::Death { player_id, card_id } => {
+                    let i = board(player_id);
+                    let index = boards[i].iter().position(|card| card.id == card_id);
+                    prop_assert!(index.is_some(), "{} of a card off the board", event);
+                    prop_assert_eq!(boards[i][index.unwrap()].hp, 0, "{} of a living card", event);
+                    boards[i].remove(index.unwrap());
+                }
+                CombatEvents::Summon { player_id, card_index, card } => {
+                    let i = board(player_id);
+                    prop_assert!(card_index as usize <= boards[i].len(), "{} out of the board", event);
+                    boards[i].insert(card_index as usize, card);
+                }
+                _ => {}
+            }
+        }
+        let [board1, board2] = boards;
+        Ok((board1, board2))
+    }
+
+    proptest! {
+        #[test]
+        fn combat_invariants(hb1 in half_board_strategy(0), hb2 in half_board_strategy(1), left_to_right: bool, seed: u64) {
+            let rules = CombatRules {
+                attack_order: if left_to_right { AttackOrder::LeftToRight } else { AttackOrder::LeastPlayed },
+                ..Default::default()
+            };
+            let result = simulate_combat(hb1.clone(), hb2.clone(), &rules, &mut StdRng::seed_from_u64(seed));
+
+            let attacks = result.events.iter().filter(|event| matches!(event, CombatEvents::Attack { .. })).count();
+            prop_assert!(attacks <= 2 * MAX_ATTACKS);
+
+            // No card with 0 HP remains
+            let (board1, board2) = follow_events(&hb1, &hb2, &result.events)?;
+            prop_assert!(board1.iter().chain(board2.iter()).all(|card| card.hp > 0));
+
+            // At most one board stands, unless none of its cards can deal damage
+            match result.outcome {
+                CombatOutcome::Winner(id) => {
+                    let (winner, loser) = if id == hb1.id { (&board1, &board2) } else { (&board2, &board1) };
+                    prop_assert!(!winner.is_empty() && loser.is_empty());
+                }
+                CombatOutcome::Draw => {
+                    prop_assert!(board1.is_empty() == board2.is_empty());
+                    prop_assert!(board1.iter().chain(board2.iter()).all(|card| card.atk == 0));
+                }
+            }
+            let players_attacks = result.events.iter().filter(|event| matches!(event, CombatEvents::PlayersAttack { .. })).count();
+            prop_assert_eq!(players_attacks, if result.outcome == CombatOutcome::Draw { 0 } else { 1 });
+
+            // Only the cards of the players come back, alive
+            for (start, end) in [(&hb1, &result.hb1), (&hb2, &result.hb2)].iter() {
+                prop_assert!(end.board.iter().all(|card| card.hp > 0 && start.board.iter().any(|c| c.id == card.id)));
+                prop_assert!(end.hp <= start.hp);
+            }
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use crate::card::Card;
+use crate::combat::{CombatEvents, CombatOutcome, HalfBoard};
+
+/// Turns combat events into sentences for the players.
+/// The boards are followed event after event to name the cards.
+pub struct CombatLog {
+    players: Vec<(u16, String, Vec<Card>)>,
+}
+
+impl CombatLog {
+    pub fn new(hb1: &HalfBoard, name1: &str, hb2: &HalfBoard, name2: &str) -> Self {
+        CombatLog {
+            players: vec![
+                (hb1.id, name1.to_string(), hb1.board.clone()),
+                (hb2.id, name2.to_string(), hb2.board.clone()),
+            ],
+        }
+    }
+
+    fn player(&self, player_id: u16) -> &(u16, String, Vec<Card>) {
+        self.players.iter().find(|(id, _, _)| *id == player_id).expect("Unknown player")
+    }
+
+    fn opponent(&self, player_id: u16) -> &(u16, String, Vec<Card>) {
+        self.players.iter().find(|(id, _, _)| *id != player_id).expect("Unknown opponent")
+    }
+
+    fn name(&self, player_id: u16) -> &str {
+        &self.player(player_id).1
+    }
+
+    fn card_at(&self, player_id: u16, index: u8) -> &str {
+        match self.player(player_id).2.get(index as usize) {
+            Some(card) => card.base_card.name(),
+            None => "?",
+        }
+    }
+
+    fn card_with_id(&self, player_id: u16, card_id: u32) -> &str {
+        match self.player(player_id).2.iter().find(|card| card.id == card_id) {
+            Some(card) => card.base_card.name(),
+            None => "?",
+        }
+    }
+
+    /// Sentence describing the event, if it is worth one.
+    pub fn line(&mut self, event: &CombatEvents) -> Option<String> {
+        let line = match *event {
+            CombatEvents::Attack { att_id, att_card_index, def_card_index } => {
+                let def_id = self.opponent(att_id).0;
+                Some(format!("{}: {} attacks {}", self.name(att_id), self.card_at(att_id, att_card_index), self.card_at(def_id, def_card_index)))
+            }
+            CombatEvents::AllyAttack { player_id, att_card_index, def_card_index } => {
+                Some(format!("{}: {} attacks its ally {}", self.name(player_id), self.card_at(player_id, att_card_index), self.card_at(player_id, def_card_index)))
+            }
+            CombatEvents::EndOfAttack { .. } | CombatEvents::EndOfAllyAttack { .. } => None,
+            CombatEvents::StartOfCombat | CombatEvents::EndOfCombat => None,
+            CombatEvents::TriggerLimit { dropped } => Some(format!("Too many abilities in a row, {} are ignored", dropped)),
+            CombatEvents::ApplyAbility { player_id, ability, card_id, .. } => {
+                Some(format!("{}: {} uses {}", self.name(player_id), self.card_with_id(player_id, card_id), ability))
+            }
+            CombatEvents::StatsChange { player_id, card_id, hp, at, .. } => {
+                let mut changes = vec![];
+                if at != 0 { changes.push(format!("{:+} ATK", at)); }
+                if hp != 0 { changes.push(format!("{:+} HP", hp)); }
+                if changes.is_empty() {
+                    None
+                } else {
+                    Some(format!("{}: {} {}", self.name(player_id), self.card_with_id(player_id, card_id), changes.join(" ")))
+                }
+            }
+            CombatEvents::Death { player_id, card_id } => {
+                Some(format!("{}: {} dies", self.name(player_id), self.card_with_id(player_id, card_id)))
+            }
+            CombatEvents::Summon { player_id, card, .. } => {
+                Some(format!("{}: {} is summoned", self.name(player_id), card.base_card.name()))
+            }
+            CombatEvents::ShieldBroken { player_id, card_id } => {
+                Some(format!("{}: {}'s shield breaks", self.name(player_id), self.card_with_id(player_id, card_id)))
+            }
+            CombatEvents::GoldChange { player_id, change } => {
+                Some(format!("{}: {:+} gold", self.name(player_id), change))
+            }
+            CombatEvents::PlayersAttack { att_id, change_def_hp } => {
+                let def_id = self.opponent(att_id).0;
+                Some(format!("{} takes {} damage", self.name(def_id), -change_def_hp))
+            }
+        };
+
+        // Follow the boards
+        match *event {
+            CombatEvents::Death { player_id, card_id } => {
+                if let Some((_, _, board)) = self.players.iter_mut().find(|(id, _, _)| *id == player_id) {
+                    board.retain(|card| card.id != card_id);
+                }
+            }
+            CombatEvents::Summon { player_id, card_index, card } => {
+                if let Some((_, _, board)) = self.players.iter_mut().find(|(id, _, _)| *id == player_id) {
+                    let index = (card_index as usize).min(board.len());
+                    board.insert(index, card);
+                }
+            }
+            _ => {}
+        }
+
+        line
+    }
+
+    pub fn outcome(&self, outcome: CombatOutcome) -> String {
+        match outcome {
+            CombatOutcome::Winner(player_id) => format!("{} wins the fight", self.name(player_id)),
+            CombatOutcome::Draw if self.players.iter().all(|(_, _, board)| board.is_empty()) => "Draw, both boards are wiped out".to_string(),
+            CombatOutcome::Draw => "Draw, neither board can win".to_string(),
+        }
+    }
+}
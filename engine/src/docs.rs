@@ -0,0 +1,125 @@
+//! Renders the card documentation from the card registry, see the `cards` binary.
+
+use crate::card::{Abilities, CardDefinition, Families, Triggers, registry};
+
+const FAMILIES: [Families; 4] = [Families::Mushrooms, Families::Merchants, Families::Spiders, Families::Robots];
+
+/// Triggers explained in `cards.md`, in this order
+const TRIGGERS: [(Triggers, &str); 9] = [
+    (Triggers::Turn, "At the beginning of each turn, from next turn"),
+    (Triggers::Played, "When this card is played"),
+    (Triggers::Death, "When this card dies"),
+    (Triggers::Survived, "When this card attacks and survives"),
+    (Triggers::Hit, "When this card attacks or is attacked"),
+    (Triggers::Kill, "When this card kills an enemy"),
+    (Triggers::Sold, "When this card is sold"),
+    (Triggers::StartOfCombat, "Before the first attack of a fight"),
+    (Triggers::EndOfCombat, "After the last attack of a fight"),
+];
+
+const KEYWORDS: [(&str, &str); 3] = [
+    ("Taunt", "enemies have to attack this card"),
+    ("Shield", "absorbs the first hit taken in a fight"),
+    ("Venom", "kills every card it deals damage to"),
+];
+
+/// Start and end of the generated part of the readme
+pub const README_START: &str = "<!-- Generated by `cargo run -p ld49-engine --bin cards`, do not edit -->\n";
+pub const README_END: &str = "<!-- End of the generated part -->\n";
+
+/// Trigger in upper case, e.g. `START OF COMBAT`
+fn trigger_name(trigger: Triggers) -> String {
+    let mut name = String::new();
+    for c in trigger.to_string().chars() {
+        if c.is_uppercase() && !name.is_empty() {
+            name.push(' ');
+        }
+        name.push(c.to_ascii_uppercase());
+    }
+    name
+}
+
+fn family_cards(family: Families) -> impl Iterator<Item = &'static CardDefinition> {
+    registry().definitions().iter().filter(move |definition| definition.family == family)
+}
+
+fn effects(definition: &CardDefinition) -> String {
+    let mut effects: Vec<String> = definition.keywords.names().iter()
+        .map(|keyword| format!("**{}**", keyword.to_uppercase()))
+        .collect();
+    if definition.ability != Abilities::None {
+        effects.push(format!("({}) **{}**", definition.ability.name(), trigger_name(definition.trigger)));
+    }
+    let description = definition.description.replace('\n', " ");
+    let description = description.trim_end_matches('.');
+    match (effects.is_empty(), description.is_empty()) {
+        (true, true) => "—".to_string(),
+        (false, true) => effects.join(" "),
+        (true, false) => description.to_string(),
+        (false, false) => format!("{}: {}", effects.join(" "), description),
+    }
+}
+
+/// Contents of `cards.md`
+pub fn cards_markdown() -> String {
+    let mut md = String::new();
+    md.push_str("# Cards\n\n");
+    md.push_str("Cards have base health points (**HP**) and attack (**ATK**).\n");
+    md.push_str("They are split into four families.\n\n");
+    md.push_str("Cards can have effects triggered as follows:\n\n");
+    for (trigger, description) in TRIGGERS.iter() {
+        md.push_str(&format!("- **{}**: {}\n", trigger_name(*trigger), description));
+    }
+    md.push_str("\nand keywords:\n\n");
+    for (keyword, description) in KEYWORDS.iter() {
+        md.push_str(&format!("- **{}**: {}\n", keyword.to_uppercase(), description));
+    }
+
+    for &family in FAMILIES.iter() {
+        md.push_str(&format!("\n## {}\n\n", family));
+        md.push_str("Image | Rank | Name | ATK | HP | Effects\n");
+        md.push_str("------|------|------|-----|----|--------\n");
+        for definition in family_cards(family) {
+            md.push_str(&format!("![](assets/{}) | {} | {} | {} | {} | {}\n",
+                                 definition.art, definition.rank, definition.name, definition.atk, definition.hp, effects(definition)));
+        }
+    }
+    md
+}
+
+/// Abilities and keywords of the readme, ticked when implemented
+pub fn readme_checklist() -> String {
+    let mut md = String::new();
+    md.push_str(README_START);
+    md.push_str("## Abilities\n");
+    for &family in FAMILIES.iter() {
+        md.push_str(&format!("\n### {}\n\n", family));
+        for definition in family_cards(family) {
+            if definition.ability != Abilities::None {
+                let ticked = if definition.ability.implemented() { "x" } else { " " };
+                md.push_str(&format!("- [{}] {} {}\n", ticked, definition.ability.name(), trigger_name(definition.trigger)));
+            }
+            for keyword in definition.keywords.names() {
+                md.push_str(&format!("- [x] {} ({} keyword)\n", definition.name, keyword));
+            }
+        }
+    }
+    md.push_str("\n## Keywords\n\n");
+    md.push_str("Keywords are set on each card and honoured by the combat simulator:\n\n");
+    for (keyword, description) in KEYWORDS.iter() {
+        md.push_str(&format!("- [x] {}: {}\n", keyword, description));
+    }
+    md.push_str(README_END);
+    md
+}
+
+/// `readme` with its generated part replaced by `readme_checklist`
+pub fn update_readme(readme: &str) -> Result<String, String> {
+    let start = readme.find(README_START).ok_or("Missing the start of the generated part of the readme")?;
+    let end = readme.find(README_END).ok_or("Missing the end of the generated part of the readme")? + README_END.len();
+    if end < start {
+        return Err("The generated part of the readme ends before it starts".to_string());
+    }
+    Ok(format!("{}{}{}", &readme[..start], readme_checklist(), &readme[end..]))
+}
+
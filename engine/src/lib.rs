@@ -0,0 +1,11 @@
+//! Cards and combat rules of Unbalanced Brawl.
+//!
+//! This crate doesn't depend on Bevy so that tools, tests and bots can run
+//! [`combat::simulate_combat`] without a window or a renderer.
+
+pub mod card;
+pub mod combat;
+pub mod combat_log;
+pub mod docs;
+pub mod replay;
+pub mod winrate;
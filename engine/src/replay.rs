@@ -0,0 +1,41 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+
+use crate::combat::{CombatEvents, CombatOutcome, CombatResult, CombatRules, HalfBoard, simulate_combat};
+
+/// A fight saved to a RON file: both starting boards, the rules and the seed of its RNG, its events and outcome.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    #[serde(default)]
+    pub rules: CombatRules,
+    pub hb1: HalfBoard,
+    pub hb2: HalfBoard,
+    pub events: Vec<CombatEvents>,
+    pub outcome: CombatOutcome,
+}
+
+impl Replay {
+    /// Simulates a fight with an RNG seeded with `seed` and records it.
+    pub fn record(hb1: HalfBoard, hb2: HalfBoard, rules: CombatRules, seed: u64) -> (Replay, CombatResult) {
+        let result = simulate_combat(hb1.clone(), hb2.clone(), &rules, &mut StdRng::seed_from_u64(seed));
+        let replay = Replay { seed, rules, hb1, hb2, events: result.events.clone(), outcome: result.outcome };
+        (replay, result)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let ron = ron::ser::to_string_pretty(self, Default::default())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(path, ron)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Replay> {
+        let ron = fs::read_to_string(path)?;
+        ron::de::from_str(&ron).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
@@ -0,0 +1,80 @@
+use std::fmt::{Display, Formatter};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::card::Card;
+use crate::combat::{CombatEvents, CombatOutcome, CombatRules, HalfBoard, simulate_combat};
+
+/// Outcomes of many fights between the same two boards, seen from the first one.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct WinRate {
+    pub runs: u32,
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    /// Total damage dealt to the second player
+    pub damage_dealt: u64,
+    /// Total damage taken from the second player
+    pub damage_taken: u64,
+}
+
+impl WinRate {
+    fn percent(&self, count: u32) -> f64 {
+        if self.runs == 0 { 0. } else { 100. * count as f64 / self.runs as f64 }
+    }
+
+    pub fn win_percent(&self) -> f64 { self.percent(self.wins) }
+
+    pub fn draw_percent(&self) -> f64 { self.percent(self.draws) }
+
+    pub fn loss_percent(&self) -> f64 { self.percent(self.losses) }
+
+    pub fn average_damage_dealt(&self) -> f64 {
+        if self.runs == 0 { 0. } else { self.damage_dealt as f64 / self.runs as f64 }
+    }
+
+    pub fn average_damage_taken(&self) -> f64 {
+        if self.runs == 0 { 0. } else { self.damage_taken as f64 / self.runs as f64 }
+    }
+}
+
+impl Display for WinRate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} fights", self.runs)?;
+        writeln!(f, "win {:.1}% / draw {:.1}% / loss {:.1}%", self.win_percent(), self.draw_percent(), self.loss_percent())?;
+        write!(f, "average damage dealt {:.2}, taken {:.2}", self.average_damage_dealt(), self.average_damage_taken())
+    }
+}
+
+/// Fights `board1` against `board2` `runs` times, the n-th fight using the seed `seed + n`.
+/// Card ids must be unique across both boards.
+/// Players have enough HP for the damage never to be capped.
+pub fn estimate_win_rate(board1: &[Card], board2: &[Card], rules: &CombatRules, runs: u32, seed: u64) -> WinRate {
+    let mut win_rate = WinRate { runs, ..Default::default() };
+
+    for run in 0..runs {
+        let hb1 = HalfBoard { id: 0, hp: u16::MAX, extra_coins: 0, shop_level: 1, board: board1.to_vec() };
+        let hb2 = HalfBoard { id: 1, hp: u16::MAX, extra_coins: 0, shop_level: 1, board: board2.to_vec() };
+        let mut rng = StdRng::seed_from_u64(seed.wrapping_add(run as u64));
+        let result = simulate_combat(hb1, hb2, rules, &mut rng);
+
+        let damage = result.events.iter().find_map(|e| match e {
+            CombatEvents::PlayersAttack { change_def_hp, .. } => Some(change_def_hp.unsigned_abs() as u64),
+            _ => None,
+        }).unwrap_or(0);
+        match result.outcome {
+            CombatOutcome::Winner(0) => {
+                win_rate.wins += 1;
+                win_rate.damage_dealt += damage;
+            }
+            CombatOutcome::Winner(_) => {
+                win_rate.losses += 1;
+                win_rate.damage_taken += damage;
+            }
+            CombatOutcome::Draw => win_rate.draws += 1,
+        }
+    }
+
+    win_rate
+}
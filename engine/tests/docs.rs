@@ -0,0 +1,22 @@
+//! `cards.md` and the readme checklist must follow the card definitions,
+//! regenerate them with `cargo run -p ld49-engine --bin cards`.
+
+use std::fs;
+use std::path::Path;
+
+use ld49_engine::docs::{cards_markdown, update_readme};
+
+fn read(file: &str) -> String {
+    fs::read_to_string(Path::new(env!("CARGO_MANIFEST_DIR")).join("..").join(file)).unwrap()
+}
+
+#[test]
+fn cards_md_is_up_to_date() {
+    assert!(read("cards.md") == cards_markdown(), "cards.md is out of date, run `cargo run -p ld49-engine --bin cards`");
+}
+
+#[test]
+fn readme_checklist_is_up_to_date() {
+    let readme = read("readme.md");
+    assert!(update_readme(&readme).unwrap() == readme, "readme.md is out of date, run `cargo run -p ld49-engine --bin cards`");
+}
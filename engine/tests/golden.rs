@@ -0,0 +1,73 @@
+//! Golden files of combat event streams.
+//!
+//! Each `tests/combats/<name>.ron` fight is simulated and its events, one per line,
+//! are compared with `tests/combats/<name>.txt`.
+//! Regenerate the golden files with `cargo make update-golden`
+//! (or `UPDATE_GOLDEN=1 cargo test -p ld49-engine --test golden`) and review the diff.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::Deserialize;
+
+use ld49_engine::card::parse_board;
+use ld49_engine::combat::{CombatRules, HalfBoard, simulate_combat};
+
+/// A fight of the corpus, boards being written as for the winrate CLI.
+#[derive(Deserialize)]
+struct GoldenCombat {
+    seed: u64,
+    #[serde(default)]
+    rules: CombatRules,
+    board1: String,
+    board2: String,
+}
+
+fn combats_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("combats")
+}
+
+fn events_text(combat: &GoldenCombat) -> String {
+    let mut next_card_id = 0;
+    let board1 = parse_board(&combat.board1, &mut next_card_id).expect("Invalid first board");
+    let board2 = parse_board(&combat.board2, &mut next_card_id).expect("Invalid second board");
+    let hb1 = HalfBoard { id: 0, hp: 20, extra_coins: 0, shop_level: 1, board: board1 };
+    let hb2 = HalfBoard { id: 1, hp: 20, extra_coins: 0, shop_level: 1, board: board2 };
+    let result = simulate_combat(hb1, hb2, &combat.rules, &mut StdRng::seed_from_u64(combat.seed));
+
+    let mut text = String::new();
+    for event in &result.events {
+        text.push_str(&format!("{}\n", event));
+    }
+    text.push_str(&format!("Outcome: {:?}\n", result.outcome));
+    text
+}
+
+#[test]
+fn combat_events_match_golden_files() {
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let mut paths: Vec<PathBuf> = fs::read_dir(combats_dir()).expect("Cannot read tests/combats")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ron"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "No combat in tests/combats");
+
+    let mut mismatches = vec![];
+    for path in paths {
+        let combat: GoldenCombat = ron::from_str(&fs::read_to_string(&path).unwrap())
+            .unwrap_or_else(|e| panic!("Invalid combat {}: {}", path.display(), e));
+        let text = events_text(&combat);
+        let golden_path = path.with_extension("txt");
+
+        if update {
+            fs::write(&golden_path, text).unwrap();
+        } else if fs::read_to_string(&golden_path).ok().as_deref() != Some(text.as_str()) {
+            mismatches.push(golden_path.display().to_string());
+        }
+    }
+
+    assert!(mismatches.is_empty(), "Events differ from the golden files, run `cargo make update-golden` and review the diff:\n{}", mismatches.join("\n"));
+}
@@ -1,21 +1,24 @@
-use std::cmp::max;
+use std::cmp::min;
 
+use bevy::input::mouse::MouseWheel;
 use bevy::math::vec3;
 use bevy::prelude::*;
 use bevy_kira_audio::{Audio, AudioChannel};
 use derive_more::Display;
+use ld49_engine::combat::{CombatEvents, CombatOutcome, CombatResult, CombatRules};
+use ld49_engine::combat_log::CombatLog;
+use ld49_engine::replay::Replay;
 use rand::Rng;
 
 use crate::{AppState, GlobalData, HEIGHT, MySelf, PlayerData, WIDTH};
-use crate::fight::abs::{CombatEvents, simulate_combat};
-use crate::data::card::{Abilities, Card, CARD_HEIGHT, CARD_SCALE, CARD_WIDTH};
+use crate::data::card::{Card, CARD_HEIGHT, CARD_SCALE, CARD_WIDTH, CardArt, CardHandle};
 use crate::data::font::TextStyles;
-use crate::data::loading::{AudioAssets, TextureAssets};
-use crate::game_over::Won;
+use crate::data::loading::{AudioAssets, ColorAssets, TextureAssets};
+use crate::game_over::RunOutcome;
 use crate::ui::StateBackground;
-use crate::ui::transition::{easing, TranslationAnimation};
+use crate::ui::transition::{easing, RemoveAfter, TranslationAnimation};
 use crate::ui::card_overlay::{NewCard, StatsChanged};
-use crate::util::{ANIM_DURATION, card_transform, cleanup_system, Corners, Level, relu, text_bundle_at_corner, Z_ABILITY, Z_BACKGROUND, Z_CARD, Z_CARD_DRAG};
+use crate::util::{ANIM_DURATION, card_transform, cleanup_system, Corners, Level, relu, text_bundle_at_corner, Z_ABILITY, Z_ANNOUNCEMENT_BG, Z_BACKGROUND, Z_CARD, Z_CARD_DRAG};
 
 pub struct FightPlugin;
 
@@ -28,6 +31,9 @@ impl Plugin for FightPlugin {
             .add_event::<ApplyEffect>()
             .add_event::<PlayersAttack>()
             .add_event::<GoldChange>()
+            .add_event::<Summon>()
+            .add_event::<Announcement>()
+            .init_resource::<FightPlayback>()
             .add_system_set(
                 SystemSet::on_enter(AppState::Fight)
                     .with_system(setup_fight.system().label("setup_fight"))
@@ -35,8 +41,12 @@ impl Plugin for FightPlugin {
             )
             .add_system_set(
                 SystemSet::on_update(AppState::Fight)
+                    .with_system(playback_controls.system().before("event_dispatcher"))
+                    .with_system(skip_to_result.system().before("event_dispatcher"))
+                    .with_system(freeze_while_paused.system().before("event_dispatcher"))
                     .with_system(event_dispatcher.system().label("event_dispatcher"))
                     .with_system(update_ui.system().label("update_ui"))
+                    .with_system(update_combat_log.system().after("event_dispatcher"))
             )
             .add_system_set(
                 SystemSet::on_update(AppState::Fight).after("event_dispatcher")
@@ -46,6 +56,8 @@ impl Plugin for FightPlugin {
                     .with_system(apply_effect_producer.system())
                     .with_system(players_attack_producer.system())
                     .with_system(gold_change_producer.system())
+                    .with_system(summon_producer.system())
+                    .with_system(announcement_producer.system())
             )
             .add_system_set(
                 SystemSet::on_exit(AppState::Fight)
@@ -56,11 +68,16 @@ impl Plugin for FightPlugin {
                     .after("on-exit").label("cleanup")
                     .with_system(cleanup_system::<FightSlot>.system())
                     .with_system(cleanup_system::<FightEventsStack>.system())
+                    .with_system(cleanup_system::<FightBoards>.system())
                     .with_system(cleanup_system::<StateBackground>.system())
                     .with_system(cleanup_system::<ExtraCoins>.system())
                     .with_system(cleanup_system::<Level>.system())
                     .with_system(cleanup_system::<MyHP>.system())
                     .with_system(cleanup_system::<FoeHP>.system())
+                    .with_system(cleanup_system::<CombatLogText>.system())
+                    .with_system(cleanup_system::<PlaybackText>.system())
+                    .with_system(cleanup_system::<FightReplay>.system())
+                    .with_system(cleanup_system::<AnnouncementPopup>.system())
                     .with_system(cleanup_system::<FightBackup>.system())
             )
         ;
@@ -112,35 +129,116 @@ impl FightSlot {
 
 pub struct MyFoe;
 
+/// Card entities of each player, in the order of the simulator's boards.
+/// A card's index in the simulator is its index here.
+pub struct FightBoards {
+    my_board: Vec<Entity>,
+    foe_board: Vec<Entity>,
+}
+
+impl FightBoards {
+    fn board(&self, who: FightPlayers) -> &Vec<Entity> {
+        match who {
+            FightPlayers::MySelf => &self.my_board,
+            FightPlayers::MyFoe => &self.foe_board,
+        }
+    }
+
+    fn board_mut(&mut self, who: FightPlayers) -> &mut Vec<Entity> {
+        match who {
+            FightPlayers::MySelf => &mut self.my_board,
+            FightPlayers::MyFoe => &mut self.foe_board,
+        }
+    }
+}
+
 pub struct FightBackup {
     who: FightPlayers,
 }
 
+/// Events left to play, with their line in the combat log
 pub struct FightEventsStack {
-    stack: Vec<FightEvents>,
+    stack: Vec<(FightEvents, Option<String>)>,
+    outcome: CombatOutcome,
+}
+
+/// Lines of the combat log, scrolled with the mouse wheel
+struct CombatLogText {
+    lines: Vec<String>,
+    // Number of lines hidden at the bottom
+    scroll: usize,
+}
+
+const LOG_LINES: usize = 8;
+
+/// Playback of the fight, kept from one fight to the next
+pub struct FightPlayback {
+    speed: f64,
+    paused: bool,
+}
+
+impl Default for FightPlayback {
+    fn default() -> Self {
+        FightPlayback { speed: 1., paused: false }
+    }
+}
+
+impl FightPlayback {
+    /// Real duration of a wait or an animation lasting `duration` at 1x
+    fn duration(&self, duration: f64) -> f64 {
+        duration / self.speed
+    }
+
+    fn next_speed(&mut self) {
+        self.speed = match self.speed as u8 {
+            1 => 2.,
+            2 => 4.,
+            _ => 1.,
+        };
+    }
+}
+
+struct PlaybackText;
+
+/// Set when the game is started with `--replay <file>` until the fight is watched: the title screen then leads to this fight.
+pub struct ReplayViewer(pub Replay);
+
+/// The fight being played, saved with R
+struct FightReplay {
+    replay: Replay,
+    saved: Option<String>,
 }
 
-fn add_card(card: Card, slot: FightSlot, commands: &mut Commands, handles: &Res<TextureAssets>, ev_new_card: &mut EventWriter<NewCard>) {
+fn add_card(card: Card, slot: FightSlot, commands: &mut Commands, card_art: &Res<CardArt>, ev_new_card: &mut EventWriter<NewCard>) -> Entity {
     let id = commands
         .spawn_bundle(SpriteBundle {
-            material: card.base_card.handle(&handles),
+            material: card.base_card.handle(card_art),
             transform: card_transform(slot.x(), slot.y()),
             ..Default::default()
         })
         .insert(card)
         .insert(slot)
         .id();
+    if card.golden {
+        commands.entity(id).with_children(|parent| {
+            parent.spawn_bundle(card_art.golden_frame());
+        });
+    }
     ev_new_card.send(NewCard(id, card.clone()));
+    return id;
 }
 
 fn setup_fight(
     mut commands: Commands,
-    handles: Res<TextureAssets>,
+    card_art: Res<CardArt>,
     time: Res<Time>,
+    mut playback: ResMut<FightPlayback>,
     mut global_data: ResMut<GlobalData>,
     mut ev_new_card: EventWriter<NewCard>,
     audio: Res<Audio>,
     songs: Res<AudioAssets>,
+    replay_viewer: Option<Res<ReplayViewer>>,
+    rules: Res<CombatRules>,
     queries: QuerySet<(
         Query<(Entity, &PlayerData), With<MySelf>>,
         Query<(Entity, &PlayerData), With<MyFoe>>,
@@ -151,125 +249,128 @@ fn setup_fight(
 
     let (_e_myself, myself) = queries.q0().single().expect("There should be only one player tagged MySelf");
     let mut myself_cloned = myself.clone();
-    let myself_cloned_again = myself.clone();
+    let myself_half_board = myself.half_board();
 
     let (_e_my_foe, my_foe) = queries.q1().single().expect("There should be only one player tagged MyFoe");
     let mut my_foe_cloned = my_foe.clone();
-    let my_foe_cloned_again = my_foe.clone();
+    let my_foe_half_board = my_foe.half_board();
 
     let my_id = myself_cloned.id;
 
-    let mut index = 0u8;
-    for &card in &myself_cloned.board {
-        add_card(card, FightSlot { who: FightSlotHeight::MySelf, index },
-                 &mut commands, &handles, &mut ev_new_card);
-        index += 1;
+    let mut my_board = vec![];
+    for (index, &card) in myself_cloned.board.iter().enumerate() {
+        my_board.push(add_card(card, FightSlot { who: FightSlotHeight::MySelf, index: index as u8 },
+                               &mut commands, &card_art, &mut ev_new_card));
     }
 
-    let mut index = 0u8;
-    for &card in &my_foe_cloned.board {
-        add_card(card, FightSlot { who: FightSlotHeight::MyFoe, index },
-                 &mut commands, &handles, &mut ev_new_card);
-        index += 1;
+    let mut foe_board = vec![];
+    for (index, &card) in my_foe_cloned.board.iter().enumerate() {
+        foe_board.push(add_card(card, FightSlot { who: FightSlotHeight::MyFoe, index: index as u8 },
+                                &mut commands, &card_art, &mut ev_new_card));
     }
 
-    let events = simulate_combat(myself_cloned_again, my_foe_cloned_again, &mut global_data.rng);
+    commands.spawn().insert(FightBoards { my_board, foe_board });
+
+    let mut log = CombatLog::new(&myself_half_board, &myself.name, &my_foe_half_board, &my_foe.name);
+    let replay = match replay_viewer {
+        Some(viewer) => viewer.0.clone(),
+        None => {
+            let seed = global_data.rng.gen();
+            let (replay, CombatResult { hb1, hb2, .. }) = Replay::record(myself_half_board, my_foe_half_board, CombatRules { turn: global_data.turn, ..*rules }, seed);
+            myself_cloned.set_half_board(hb1);
+            my_foe_cloned.set_half_board(hb2);
+
+            // A replay doesn't change the players
+            commands.spawn()
+                .insert(myself_cloned)
+                .insert(FightBackup { who: FightPlayers::MySelf });
+            commands.spawn()
+                .insert(my_foe_cloned)
+                .insert(FightBackup { who: FightPlayers::MyFoe });
+            replay
+        }
+    };
 
-    let mut stack = Vec::with_capacity(events.len());
-    for e in events {
-        match e {
+    let mut stack: Vec<(FightEvents, Option<String>)> = Vec::with_capacity(replay.events.len());
+    for e in replay.events.iter().cloned() {
+        let line = log.line(&e);
+        let fight_event = match e {
             CombatEvents::Attack { att_id, att_card_index, def_card_index } => {
                 let att = if att_id == my_id { FightPlayers::MySelf } else { FightPlayers::MyFoe };
-                let att_base = FightSlot { who: to_base_height(att), index: att_card_index };
                 let att_post = FightSlot { who: to_fighting_height(att), index: def_card_index };
                 // Translation to fight
-                stack.push(FightEvents::Translation(Translation { from: att_base, to: att_post }));
+                FightEvents::Translation(Translation { who: att, index: att_card_index, to: att_post })
             }
-            CombatEvents::EndOfAttack { att_id, att_card_index, def_card_index } => {
+            CombatEvents::EndOfAttack { att_id, att_card_index, def_card_index: _ } => {
                 let att = if att_id == my_id { FightPlayers::MySelf } else { FightPlayers::MyFoe };
                 let att_base = FightSlot { who: to_base_height(att), index: att_card_index };
-                let att_post = FightSlot { who: to_fighting_height(att), index: def_card_index };
-                // Translation to fight
-                stack.push(FightEvents::Translation(Translation { from: att_post, to: att_base }));
+                // Translation back to the board
+                FightEvents::Translation(Translation { who: att, index: att_card_index, to: att_base })
+            }
+            CombatEvents::AllyAttack { player_id, att_card_index, def_card_index } => {
+                let att = if player_id == my_id { FightPlayers::MySelf } else { FightPlayers::MyFoe };
+                let att_post = FightSlot { who: to_fighting_height(other_player(att)), index: def_card_index };
+                // Translation in front of the ally
+                FightEvents::Translation(Translation { who: att, index: att_card_index, to: att_post })
+            }
+            CombatEvents::EndOfAllyAttack { player_id, att_card_index, def_card_index: _ } => {
+                let att = if player_id == my_id { FightPlayers::MySelf } else { FightPlayers::MyFoe };
+                let att_base = FightSlot { who: to_base_height(att), index: att_card_index };
+                FightEvents::Translation(Translation { who: att, index: att_card_index, to: att_base })
             }
             CombatEvents::Death { player_id: _, card_id } => {
-                stack.push(FightEvents::RemoveCard(RemoveCard(card_id)));
+                FightEvents::RemoveCard(RemoveCard(card_id))
             }
-            CombatEvents::StatsChange { player_id: _, card_id, hp, at } => {
-                stack.push(FightEvents::StatsChange(StatsChange { card_id, at, hp }));
+            CombatEvents::StatsChange { player_id: _, card_id, hp, at, permanent: _ } => {
+                FightEvents::StatsChange(StatsChange { card_id, at, hp, break_shield: false })
             }
-            CombatEvents::ApplyAbility { card_index: _, player_id, ability, card_id } => {
-                stack.push(FightEvents::ApplyEffect(ApplyEffect(card_id)));
-
-                match ability {
-                    Abilities::Gigantism => {
-                        if player_id == my_id {
-                            for mut card in myself_cloned.board.iter_mut() {
-                                if card.id == card_id {
-                                    card.atk += 1;
-                                }
-                            }
-                        }
-                    }
-                    Abilities::Glitch => {
-                        for mut card in myself_cloned.board.iter_mut() {
-                            if card.id == card_id {
-                                if global_data.rng.gen() {
-                                    card.atk = max(card.atk as i32 - 2, 0) as u16;
-                                } else {
-                                    card.hp = max(card.hp as i32 - 2, 0) as u16;
-                                }
-                            }
-                        }
-                        for mut card in my_foe_cloned.board.iter_mut() {
-                            if card.id == card_id {
-                                if global_data.rng.gen() {
-                                    card.atk = max(card.atk as i32 - 2, 0) as u16;
-                                } else {
-                                    card.hp = max(card.hp as i32 - 2, 0) as u16;
-                                }
-                            }
-                        }
-                        myself_cloned.board.retain(|card| card.hp > 0);
-                        my_foe_cloned.board.retain(|card| card.hp > 0);
-                    }
-                    _ => {}
-                }
+            CombatEvents::ShieldBroken { player_id: _, card_id } => {
+                FightEvents::StatsChange(StatsChange { card_id, at: 0, hp: 0, break_shield: true })
+            }
+            CombatEvents::ApplyAbility { card_index: _, player_id: _, ability: _, card_id } => {
+                FightEvents::ApplyEffect(ApplyEffect(card_id))
             }
             CombatEvents::GoldChange { player_id, change } => {
-                let who = if player_id == my_id {
-                    myself_cloned.extra_coins = (myself_cloned.extra_coins as i32 + change) as u16;
-                    FightPlayers::MySelf
-                } else {
-                    my_foe_cloned.extra_coins = (my_foe_cloned.extra_coins as i32 + change) as u16;
-                    FightPlayers::MyFoe
-                };
-                stack.push(FightEvents::GoldChange(GoldChange { who, change }));
+                let who = if player_id == my_id { FightPlayers::MySelf } else { FightPlayers::MyFoe };
+                FightEvents::GoldChange(GoldChange { who, change })
+            }
+            CombatEvents::Summon { player_id, card_index, card } => {
+                let who = if player_id == my_id { FightPlayers::MySelf } else { FightPlayers::MyFoe };
+                FightEvents::Summon(Summon { who, index: card_index, card })
             }
             CombatEvents::PlayersAttack { att_id, change_def_hp } => {
-                let on = if att_id == my_id {
-                    my_foe_cloned.hp = (my_foe_cloned.hp as i32 + change_def_hp) as u16;
-                    FightPlayers::MyFoe
-                } else {
-                    myself_cloned.hp = (myself_cloned.hp as i32 + change_def_hp) as u16;
-                    FightPlayers::MySelf
-                };
-                stack.push(FightEvents::PlayersAttack(PlayersAttack { on, change: change_def_hp }))
+                let on = if att_id == my_id { FightPlayers::MyFoe } else { FightPlayers::MySelf };
+                FightEvents::PlayersAttack(PlayersAttack { on, change: change_def_hp })
             }
-        }
+            // Phases are only seen through the effects that follow them
+            CombatEvents::StartOfCombat | CombatEvents::EndOfCombat => continue,
+            // Nothing to animate, the line is shown with the last effect
+            CombatEvents::TriggerLimit { .. } => {
+                if let (Some(line), Some((_, last_line))) = (line, stack.last_mut()) {
+                    *last_line = Some(match last_line.take() {
+                        Some(last_line) => format!("{}\n{}", last_line, line),
+                        None => line,
+                    });
+                }
+                continue;
+            }
+        };
+        stack.push((fight_event, line));
     }
 
-    stack.reverse();
+    let announcement = match replay.outcome {
+        CombatOutcome::Winner(id) if id == my_id => "VICTORY",
+        CombatOutcome::Winner(_) => "DEFEAT",
+        CombatOutcome::Draw => "DRAW",
+    };
+    stack.push((FightEvents::Announcement(Announcement(announcement)), Some(log.outcome(replay.outcome))));
 
-    commands.spawn()
-        .insert(myself_cloned)
-        .insert(FightBackup { who: FightPlayers::MySelf });
-    commands.spawn()
-        .insert(my_foe_cloned)
-        .insert(FightBackup { who: FightPlayers::MyFoe });
+    stack.reverse();
 
-    commands.spawn().insert(FightEventsStack { stack });
-    commands.spawn().insert(WaitUntil(time.seconds_since_startup() + 2.));
+    commands.spawn().insert(FightEventsStack { stack, outcome: replay.outcome });
+    commands.spawn().insert(FightReplay { replay, saved: None });
+    playback.paused = false;
+    commands.spawn().insert(WaitUntil(time.seconds_since_startup() + playback.duration(2.)));
 }
 
 fn draw_fight(
@@ -319,6 +420,31 @@ fn draw_fight(
             &text_styles.love_bug_small,
         )
     ).insert(FoeHP);
+
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect { top: Val::Px(110.), left: Val::Px(16.), ..Default::default() },
+                max_size: Size::new(Val::Px(180.), Val::Undefined),
+                ..Default::default()
+            },
+            text: Text::with_section("", text_styles.bird_seed_small.clone(), Default::default()),
+            ..Default::default()
+        })
+        .insert(CombatLogText { lines: vec![], scroll: 0 });
+
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect { bottom: Val::Px(80.), right: Val::Px(32.), ..Default::default() },
+                ..Default::default()
+            },
+            text: Text::with_section("", text_styles.bird_seed_small.clone(), Default::default()),
+            ..Default::default()
+        })
+        .insert(PlaybackText);
 }
 
 fn to_base_height(p: FightPlayers) -> FightSlotHeight {
@@ -343,16 +469,18 @@ fn to_fighting_height(p: FightPlayers) -> FightSlotHeight {
 //         FightSlotHeight::FightingMyFoe => FightPlayers::MySelf,
 //     }
 // }
-//
-// fn other_player(p: FightPlayers) -> FightPlayers {
-//     match p {
-//         FightPlayers::MySelf => FightPlayers::MyFoe,
-//         FightPlayers::MyFoe => FightPlayers::MySelf,
-//     }
-// }
 
+fn other_player(p: FightPlayers) -> FightPlayers {
+    match p {
+        FightPlayers::MySelf => FightPlayers::MyFoe,
+        FightPlayers::MyFoe => FightPlayers::MySelf,
+    }
+}
+
+/// Moves the card at `index` in the board of `who` to `to`
 struct Translation {
-    from: FightSlot,
+    who: FightPlayers,
+    index: u8,
     to: FightSlot,
 }
 
@@ -362,6 +490,7 @@ struct StatsChange {
     card_id: u32,
     hp: i32,
     at: i32,
+    break_shield: bool,
 }
 
 struct ApplyEffect(u32);
@@ -376,6 +505,18 @@ struct PlayersAttack {
     change: i32,
 }
 
+struct Summon {
+    who: FightPlayers,
+    index: u8,
+    card: Card,
+}
+
+struct Announcement(&'static str);
+
+struct AnnouncementPopup;
+
+const ANNOUNCEMENT_DURATION: f64 = 1.5;
+
 enum FightEvents {
     Translation(Translation),
     RemoveCard(RemoveCard),
@@ -383,27 +524,183 @@ enum FightEvents {
     ApplyEffect(ApplyEffect),
     PlayersAttack(PlayersAttack),
     GoldChange(GoldChange),
+    Summon(Summon),
+    Announcement(Announcement),
+}
+
+/// SPACE pauses the fight, S changes its speed and R saves a replay, ENTER is handled by `skip_to_result`.
+fn playback_controls(
+    keys: Res<Input<KeyCode>>,
+    global_data: Res<GlobalData>,
+    mut playback: ResMut<FightPlayback>,
+    mut fight_replay: Query<&mut FightReplay>,
+    mut text: Query<&mut Text, With<PlaybackText>>,
+) {
+    if keys.just_pressed(KeyCode::Space) {
+        playback.paused = !playback.paused;
+    }
+    if keys.just_pressed(KeyCode::S) {
+        playback.next_speed();
+    }
+
+    if let Ok(mut fight_replay) = fight_replay.single_mut() {
+        if keys.just_pressed(KeyCode::R) {
+            let file = format!("replay-{}-{}.ron", global_data.seed, global_data.turn);
+            fight_replay.saved = Some(match fight_replay.replay.save(&file) {
+                Ok(()) => format!("SAVED {}", file),
+                Err(_) => "CANNOT SAVE THE REPLAY".to_string(),
+            });
+        }
+
+        if let Ok(mut text) = text.single_mut() {
+            text.sections[0].value = format!(
+                "SPEED {}X{}\nSPACE pause, S speed\nENTER skip, R save replay{}",
+                playback.speed,
+                if playback.paused { " - PAUSED" } else { "" },
+                fight_replay.saved.as_ref().map(|saved| format!("\n{}", saved)).unwrap_or_default(),
+            );
+        }
+    }
+}
+
+/// ENTER applies every event left before the announcement at once: the boards are rebuilt as they end,
+/// and the fight goes on with the announcement.
+fn skip_to_result(
+    mut commands: Commands,
+    keys: Res<Input<KeyCode>>,
+    time: Res<Time>,
+    mut playback: ResMut<FightPlayback>,
+    mut stack: Query<&mut FightEventsStack>,
+    waits: Query<Entity, With<WaitUntil>>,
+    mut combat_log: Query<&mut CombatLogText>,
+    mut boards: Query<&mut FightBoards>,
+    cards: Query<&Card>,
+    mut players: QuerySet<(
+        Query<&mut PlayerData, With<MySelf>>,
+        Query<&mut PlayerData, With<MyFoe>>,
+    )>,
+    card_art: Res<CardArt>,
+    mut ev_new_card: EventWriter<NewCard>,
+) {
+    if !keys.just_pressed(KeyCode::Return) {
+        return;
+    }
+    let (mut stack, mut boards) = match (stack.single_mut(), boards.single_mut()) {
+        (Ok(stack), Ok(boards)) => (stack, boards),
+        _ => return,
+    };
+    if !stack.stack.iter().any(|(e, _)| matches!(e, FightEvents::Announcement(_))) {
+        return;
+    }
+
+    let mut my_cards: Vec<Card> = boards.my_board.iter().filter_map(|&e| cards.get(e).ok()).copied().collect();
+    let mut foe_cards: Vec<Card> = boards.foe_board.iter().filter_map(|&e| cards.get(e).ok()).copied().collect();
+    while !matches!(stack.stack.last(), Some((FightEvents::Announcement(_), _)) | None) {
+        let (e, line) = stack.stack.pop().unwrap();
+        if let (Some(line), Ok(mut combat_log)) = (line, combat_log.single_mut()) {
+            combat_log.lines.push(line);
+        }
+        match e {
+            // Cards end on their board
+            FightEvents::Translation(_) | FightEvents::ApplyEffect(_) => {}
+            FightEvents::RemoveCard(RemoveCard(card_id)) => {
+                my_cards.retain(|card| card.id != card_id);
+                foe_cards.retain(|card| card.id != card_id);
+            }
+            FightEvents::StatsChange(StatsChange { card_id, hp, at, break_shield }) => {
+                for card in my_cards.iter_mut().chain(foe_cards.iter_mut()).filter(|card| card.id == card_id) {
+                    if break_shield {
+                        card.keywords.shield = false;
+                    }
+                    card.hp = relu(card.hp as i32 + hp);
+                    card.atk = relu(card.atk as i32 + at);
+                }
+            }
+            FightEvents::Summon(Summon { who, index, card }) => {
+                let board = if who == FightPlayers::MySelf { &mut my_cards } else { &mut foe_cards };
+                board.insert(min(index as usize, board.len()), card);
+            }
+            FightEvents::PlayersAttack(PlayersAttack { on, change }) => {
+                if let Ok(mut data) = if on == FightPlayers::MySelf { players.q0_mut().single_mut() } else { players.q1_mut().single_mut() } {
+                    data.hp = relu(data.hp as i32 + change);
+                }
+            }
+            FightEvents::GoldChange(GoldChange { who, change }) => {
+                if let Ok(mut data) = if who == FightPlayers::MySelf { players.q0_mut().single_mut() } else { players.q1_mut().single_mut() } {
+                    data.extra_coins = relu(data.extra_coins as i32 + change);
+                }
+            }
+            FightEvents::Announcement(_) => unreachable!(),
+        }
+    }
+
+    for &e in boards.my_board.iter().chain(boards.foe_board.iter()) {
+        commands.entity(e).despawn_recursive();
+    }
+    for (who, cards) in [(FightPlayers::MySelf, my_cards), (FightPlayers::MyFoe, foe_cards)].iter() {
+        *boards.board_mut(*who) = cards.iter().enumerate()
+            .map(|(index, &card)| add_card(card, FightSlot { who: to_base_height(*who), index: index as u8 }, &mut commands, &card_art, &mut ev_new_card))
+            .collect();
+    }
+
+    for e in waits.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+    commands.spawn().insert(WaitUntil(time.seconds_since_startup()));
+    playback.paused = false;
+}
+
+/// Running animations and waits are pushed back while the fight is paused, so that they resume where they stopped.
+fn freeze_while_paused(
+    time: Res<Time>,
+    playback: Res<FightPlayback>,
+    mut animations: Query<&mut TranslationAnimation, With<FightSlot>>,
+    mut waits: Query<&mut WaitUntil>,
+    mut popups: Query<&mut RemoveAfter, With<AnnouncementPopup>>,
+) {
+    if !playback.paused {
+        return;
+    }
+    let delta = time.delta_seconds_f64();
+    for mut animation in animations.iter_mut() {
+        animation.t0 += delta;
+    }
+    for mut wait in waits.iter_mut() {
+        wait.0 += delta;
+    }
+    for mut popup in popups.iter_mut() {
+        popup.0 += delta;
+    }
 }
 
 fn event_dispatcher(
     mut commands: Commands,
     time: Res<Time>,
+    playback: Res<FightPlayback>,
+    replay_viewer: Option<Res<ReplayViewer>>,
     mut queries: QuerySet<(
         Query<&mut FightEventsStack>,
         Query<(Entity, &WaitUntil)>
     )>,
+    mut combat_log: Query<&mut CombatLogText>,
     mut ew_translation: EventWriter<Translation>,
     mut ew_remove_card: EventWriter<RemoveCard>,
     mut ew_stats_change: EventWriter<StatsChange>,
     mut ew_apply_effect: EventWriter<ApplyEffect>,
     mut ew_players_attack: EventWriter<PlayersAttack>,
     mut ew_gold_change: EventWriter<GoldChange>,
+    mut ew_summon: EventWriter<Summon>,
+    mut ew_announcement: EventWriter<Announcement>,
     players: QuerySet<(
         Query<&PlayerData, With<MySelf>>,
         Query<&PlayerData, (Without<MySelf>, Without<FightBackup>)>,
     )>,
     mut app_state: ResMut<State<AppState>>,
 ) {
+    if playback.paused {
+        return;
+    }
+
     let mut should_dispatch = false;
     for (e, WaitUntil(t0)) in queries.q1().iter() {
         if time.seconds_since_startup() > *t0 {
@@ -413,7 +710,12 @@ fn event_dispatcher(
     }
     if should_dispatch {
         let mut stack = queries.q0_mut().single_mut().expect("There should only be one stack");
-        if let Some(e) = stack.stack.pop() {
+        if let Some((e, line)) = stack.stack.pop() {
+            if let Some(line) = line {
+                if let Ok(mut combat_log) = combat_log.single_mut() {
+                    combat_log.lines.push(line);
+                }
+            }
             match e {
                 FightEvents::Translation(t) => {
                     ew_translation.send(t);
@@ -433,7 +735,20 @@ fn event_dispatcher(
                 FightEvents::GoldChange(g) => {
                     ew_gold_change.send(g);
                 }
+                FightEvents::Summon(s) => {
+                    ew_summon.send(s);
+                }
+                FightEvents::Announcement(a) => {
+                    ew_announcement.send(a);
+                }
             }
+        } else if replay_viewer.is_some() {
+            // The replay is watched once, the title screen then starts runs
+            commands.remove_resource::<ReplayViewer>();
+            app_state.set(AppState::Title);
+        } else if stack.outcome == CombatOutcome::Draw {
+            // Nobody took damage
+            app_state.set(AppState::Shop);
         } else {
             let dead = players.q0().single().unwrap().hp <= 0;
             let mut last_alive = true;
@@ -443,8 +758,14 @@ fn event_dispatcher(
                     break;
                 }
             }
-            if dead || last_alive {
-                commands.insert_resource(Won(last_alive));
+            let run_outcome = match (dead, last_alive) {
+                (true, true) => Some(RunOutcome::Draw),
+                (true, false) => Some(RunOutcome::Lost),
+                (false, true) => Some(RunOutcome::Won),
+                (false, false) => None,
+            };
+            if let Some(run_outcome) = run_outcome {
+                commands.insert_resource(run_outcome);
                 app_state.set(AppState::GameOver);
             } else {
                 app_state.set(AppState::Shop);
@@ -453,21 +774,43 @@ fn event_dispatcher(
     }
 }
 
+fn update_combat_log(
+    mut ev_scroll: EventReader<MouseWheel>,
+    mut query: Query<(&mut CombatLogText, &mut Text)>,
+) {
+    if let Ok((mut combat_log, mut text)) = query.single_mut() {
+        let max_scroll = combat_log.lines.len().saturating_sub(LOG_LINES);
+        for ev in ev_scroll.iter() {
+            if ev.y > 0. {
+                combat_log.scroll = min(combat_log.scroll + 1, max_scroll);
+            } else if ev.y < 0. {
+                combat_log.scroll = combat_log.scroll.saturating_sub(1);
+            }
+        }
+
+        let end = combat_log.lines.len() - min(combat_log.scroll, max_scroll);
+        let start = end.saturating_sub(LOG_LINES);
+        text.sections[0].value = combat_log.lines[start..end].join("\n");
+    }
+}
+
 fn translation_animation_producer(
     mut er: EventReader<Translation>,
     mut commands: Commands,
-    query: Query<(Entity, &FightSlot)>,
+    boards: Query<&FightBoards>,
+    mut slots: Query<&mut FightSlot>,
     time: Res<Time>,
+    playback: Res<FightPlayback>,
 ) {
-    for Translation { from, to } in er.iter() {
-        for (e, slot) in query.iter() {
-            if slot == from {
-                let duration = ANIM_DURATION;
+    for Translation { who, index, to } in er.iter() {
+        let boards = boards.single().expect("There should be only one fight board");
+        if let Some(&e) = boards.board(*who).get(*index as usize) {
+            if let Ok(mut slot) = slots.get_mut(e) {
+                let duration = playback.duration(ANIM_DURATION);
                 let t0 = time.seconds_since_startup();
                 commands.entity(e)
-                    .remove::<FightSlot>()
-                    .insert(translate_slots(t0, *slot, *to, duration))
-                    .insert(*to);
+                    .insert(translate_slots(t0, *slot, *to, duration));
+                *slot = *to;
                 commands.spawn()
                     .insert(WaitUntil(t0 + duration as f64));
             }
@@ -475,6 +818,26 @@ fn translation_animation_producer(
     }
 }
 
+/// Slides the cards resting on the board of `who` to the slot matching their index.
+/// Cards away fighting keep their slot until they come back.
+/// Returns true if a card moved.
+fn reflow(who: FightPlayers, boards: &FightBoards, slots: &mut Query<&mut FightSlot>, commands: &mut Commands, t0: f64, duration: f64) -> bool {
+    let height = to_base_height(who);
+    let mut translated = false;
+    for (index, &e) in boards.board(who).iter().enumerate() {
+        if let Ok(mut slot) = slots.get_mut(e) {
+            if slot.who == height && slot.index != index as u8 {
+                translated = true;
+                let start = *slot;
+                slot.index = index as u8;
+                commands.entity(e)
+                    .insert(translate_slots(t0, start, *slot, duration));
+            }
+        }
+    }
+    translated
+}
+
 fn translate_slots(t0: f64, from: FightSlot, to: FightSlot, duration: f64) -> TranslationAnimation {
     let start = vec3(from.x(), from.y(), Z_CARD_DRAG);
     let end = vec3(to.x(), to.y(), Z_CARD);
@@ -493,18 +856,22 @@ fn stat_change_producer(
     mut commands: Commands,
     mut ev_stats: EventWriter<StatsChanged>,
     time: Res<Time>,
+    playback: Res<FightPlayback>,
     audio: Res<Audio>,
     music: Res<AudioAssets>,
 ) {
     for event in er_stats_change.iter() {
         for (e, mut card) in query.iter_mut() {
             if card.id == event.card_id {
-                if event.hp < 0 {
+                if event.hp < 0 || event.break_shield {
                     audio.play_in_channel(music.attack.clone(), &AudioChannel::new("SFX".to_owned()));
                 }
+                if event.break_shield {
+                    card.keywords.shield = false;
+                }
                 card.hp = relu(card.hp as i32 + event.hp);
                 card.atk = relu(card.atk as i32 + event.at);
-                commands.spawn().insert(WaitUntil(time.seconds_since_startup() + 0.5));
+                commands.spawn().insert(WaitUntil(time.seconds_since_startup() + playback.duration(0.5)));
                 ev_stats.send(StatsChanged(e));
             }
         }
@@ -513,9 +880,12 @@ fn stat_change_producer(
 
 fn remove_card_producer(
     time: Res<Time>,
+    playback: Res<FightPlayback>,
     mut er_remove_card_event: EventReader<RemoveCard>,
     mut commands: Commands,
-    mut query: Query<(Entity, &mut FightSlot, &Card)>,
+    mut boards: Query<&mut FightBoards>,
+    cards: Query<(Entity, &Card)>,
+    mut slots: Query<&mut FightSlot>,
     audio: Res<Audio>,
     music: Res<AudioAssets>,
 ) {
@@ -526,66 +896,94 @@ fn remove_card_producer(
         return;
     }
 
-    let mut removed_slots = vec![];
-    let mut my_to_push = 0usize;
-    let mut foe_to_push = 0usize;
-    let mut used = vec![];
-    for (e, slot, &card) in query.iter_mut() {
+    let mut boards = boards.single_mut().expect("There should be only one fight board");
+    for (e, card) in cards.iter() {
         if removed_ids.contains(&card.id) {
             audio.play_in_channel(music.death.clone(), &AudioChannel::new("SFX".to_owned()));
             commands.entity(e)
                 .despawn_recursive();
-            if slot.who == FightSlotHeight::FightingMyFoe {
-                my_to_push += 1;
-            } else if slot.who == FightSlotHeight::FightingMySelf {
-                foe_to_push += 1;
-            } else {
-                removed_slots.push(slot.clone());
-            }
-        } else {
-            used.push(slot);
+            boards.my_board.retain(|&other| other != e);
+            boards.foe_board.retain(|&other| other != e);
         }
     }
 
-    while my_to_push > 0 {
-        let used_indexes: Vec<u8> = used.iter().filter(|slot| slot.who == FightSlotHeight::MySelf).map(|slot| slot.index).collect();
-        let mut first_empty = 0u8;
-        while used_indexes.contains(&first_empty) { first_empty += 1 }
-        removed_slots.push(FightSlot { who: FightSlotHeight::MySelf, index: first_empty });
-        my_to_push -= 1;
-    }
-    while foe_to_push > 0 {
-        let used_indexes: Vec<u8> = used.iter().filter(|slot| slot.who == FightSlotHeight::MyFoe).map(|slot| slot.index).collect();
-        let mut first_empty = 0u8;
-        while used_indexes.contains(&first_empty) { first_empty += 1 }
-        removed_slots.push(FightSlot { who: FightSlotHeight::MyFoe, index: first_empty });
-        foe_to_push -= 1;
+    let duration = playback.duration(ANIM_DURATION);
+    let my_translated = reflow(FightPlayers::MySelf, &boards, &mut slots, &mut commands, t0, duration);
+    let foe_translated = reflow(FightPlayers::MyFoe, &boards, &mut slots, &mut commands, t0, duration);
+    let wait_duration = if my_translated || foe_translated { duration } else { playback.duration(0.3) };
+    commands.spawn().insert(WaitUntil(t0 + wait_duration));
+}
+
+fn summon_producer(
+    mut er: EventReader<Summon>,
+    mut commands: Commands,
+    time: Res<Time>,
+    playback: Res<FightPlayback>,
+    mut boards: Query<&mut FightBoards>,
+    mut slots: Query<&mut FightSlot>,
+    card_art: Res<CardArt>,
+    mut ev_new_card: EventWriter<NewCard>,
+) {
+    let t0 = time.seconds_since_startup();
+    for &Summon { who, index, card } in er.iter() {
+        let mut boards = boards.single_mut().expect("There should be only one fight board");
+        let e = add_card(card, FightSlot { who: to_base_height(who), index }, &mut commands, &card_art, &mut ev_new_card);
+        let board = boards.board_mut(who);
+        let index = min(index as usize, board.len());
+        board.insert(index, e);
+        // Make room for the new card
+        let duration = playback.duration(ANIM_DURATION);
+        reflow(who, &boards, &mut slots, &mut commands, t0, duration);
+        commands.spawn().insert(WaitUntil(t0 + duration));
     }
+}
 
-    let mut translated = false;
-    for (e, mut slot, &card) in query.iter_mut() {
-        if !removed_ids.contains(&card.id) {
-            let removed_before: usize = removed_slots.iter().filter(|removed_slot| {
-                slot.who == removed_slot.who && removed_slot.index < slot.index
-            }).count();
-            if removed_before != 0 {
-                translated = true;
-                let start = slot.clone();
-                slot.who = start.who;
-                slot.index = start.index - removed_before as u8;
-                commands.entity(e)
-                    .insert(translate_slots(t0, start, *slot, ANIM_DURATION));
-            }
-        }
+fn announcement_producer(
+    mut er: EventReader<Announcement>,
+    mut commands: Commands,
+    time: Res<Time>,
+    playback: Res<FightPlayback>,
+    text_styles: Res<TextStyles>,
+    colors: Res<ColorAssets>,
+) {
+    for Announcement(text) in er.iter() {
+        let t1 = time.seconds_since_startup() + playback.duration(ANNOUNCEMENT_DURATION);
+        commands.spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                *text,
+                text_styles.note.clone(),
+                TextAlignment {
+                    horizontal: HorizontalAlign::Center,
+                    vertical: VerticalAlign::Center,
+                }),
+            transform: Transform {
+                translation: Vec3::new(WIDTH / 2., HEIGHT / 2., Z_ANNOUNCEMENT_BG + 1.),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+            .insert(RemoveAfter(t1))
+            .insert(AnnouncementPopup);
+        commands.spawn_bundle(SpriteBundle {
+            material: colors.black.clone(),
+            sprite: Sprite::new(Vec2::new(WIDTH / 3., HEIGHT / 6.)),
+            transform: Transform {
+                translation: Vec3::new(WIDTH / 2., HEIGHT / 2., Z_ANNOUNCEMENT_BG),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+            .insert(RemoveAfter(t1))
+            .insert(AnnouncementPopup);
+        commands.spawn().insert(WaitUntil(t1));
     }
-    let wait_duration = if translated { ANIM_DURATION } else { 0.3 };
-    commands.spawn().insert(WaitUntil(t0 + wait_duration));
 }
 
 fn apply_effect_producer(
     mut er: EventReader<ApplyEffect>,
     mut commands: Commands,
     time: Res<Time>,
+    playback: Res<FightPlayback>,
     query: Query<(Entity, &Card)>,
     handles: Res<TextureAssets>,
     audio: Res<Audio>,
@@ -608,7 +1006,7 @@ fn apply_effect_producer(
                                 },
                                 ..Default::default()
                             })
-                            .insert(WaitUntil(time.seconds_since_startup() + 1.));
+                            .insert(WaitUntil(time.seconds_since_startup() + playback.duration(1.)));
                     });
             }
         }
@@ -619,13 +1017,36 @@ fn players_attack_producer(
     mut er: EventReader<PlayersAttack>,
     mut commands: Commands,
     time: Res<Time>,
+    playback: Res<FightPlayback>,
+    text_styles: Res<TextStyles>,
+    fight_replay: Query<&FightReplay>,
     mut queries: QuerySet<(
         Query<&mut PlayerData, With<MySelf>>,
         Query<&mut PlayerData, With<MyFoe>>,
     )>,
 ) {
     for PlayersAttack { on, change } in er.iter() {
-        commands.spawn().insert(WaitUntil(time.seconds_since_startup() + 0.5));
+        let t1 = time.seconds_since_startup() + playback.duration(1.);
+        if let Ok(FightReplay { replay, .. }) = fight_replay.single() {
+            // Explain where the damage comes from
+            commands.spawn_bundle(Text2dBundle {
+                text: Text::with_section(
+                    format!("{} HP\nDamage: {}", change, replay.rules.damage),
+                    text_styles.subtitle.clone(),
+                    TextAlignment {
+                        horizontal: HorizontalAlign::Center,
+                        vertical: VerticalAlign::Center,
+                    }),
+                transform: Transform {
+                    translation: Vec3::new(WIDTH / 2., HEIGHT / 2. - 100., Z_ANNOUNCEMENT_BG + 1.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+                .insert(RemoveAfter(t1))
+                .insert(AnnouncementPopup);
+        }
+        commands.spawn().insert(WaitUntil(t1));
 
         let mut def_data =
             if *on == FightPlayers::MySelf {
@@ -675,6 +1096,7 @@ fn gold_change_producer(
     )>,
     mut commands: Commands,
     time: Res<Time>,
+    playback: Res<FightPlayback>,
 ) {
     let mut should_trigger_wait = false;
     for GoldChange { who, change } in er.iter() {
@@ -682,11 +1104,11 @@ fn gold_change_producer(
             FightPlayers::MySelf => query.q0_mut().single_mut().expect("Cannot get main player"),
             FightPlayers::MyFoe => query.q1_mut().single_mut().expect("Cannot get opponent"),
         };
-        player_data.hp = (player_data.hp as i32 + change) as u16;
+        player_data.extra_coins = relu(player_data.extra_coins as i32 + change);
         should_trigger_wait = true;
     }
     if should_trigger_wait {
-        commands.spawn().insert(WaitUntil(time.seconds_since_startup() + 0.5));
+        commands.spawn().insert(WaitUntil(time.seconds_since_startup() + playback.duration(0.5)));
     }
 }
 
@@ -1,3 +1,2 @@
-pub mod abs;
 pub mod fight_screen;
 pub mod predefined_hands;
\ No newline at end of file
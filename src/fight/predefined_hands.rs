@@ -170,6 +170,8 @@ impl HandsName {
                 hp: card_state.2,
                 atk: card_state.1,
                 played: 0,
+                keywords: card_state.0.keywords(),
+                golden: false,
             };
             global_data.next_card_id += 1;
             card
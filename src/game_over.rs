@@ -1,8 +1,9 @@
 use bevy::prelude::*;
 use bevy_kira_audio::Audio;
 
-use crate::{AppState, HEIGHT, PlayerData, WIDTH, MySelf};
+use crate::{AppState, GlobalData, HEIGHT, PlayerData, WIDTH, MySelf};
 use crate::fight::fight_screen::FightBackup;
+use crate::data::card::{CardArt, CardHandle};
 use crate::data::font::TextStyles;
 use crate::data::loading::{AudioAssets, TextureAssets};
 use crate::ui::StateBackground;
@@ -45,15 +46,23 @@ impl Plugin for GameOverPlugin {
 
 struct Over;
 
-pub struct Won(pub bool);
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RunOutcome {
+    Won,
+    Lost,
+    /// The player and their last opponent died at the same time
+    Draw,
+}
 
 fn init(
     mut commands: Commands,
     text_styles: Res<TextStyles>,
     mut player_data: Query<&PlayerData, With<MySelf>>,
     mut ev_card: EventWriter<NewCard>,
-    won: Res<Won>,
+    run_outcome: Res<RunOutcome>,
+    global_data: Res<GlobalData>,
     handles: Res<TextureAssets>,
+    card_art: Res<CardArt>,
     audio: Res<Audio>,
     songs: Res<AudioAssets>,
 ) {
@@ -84,7 +93,11 @@ fn init(
     }).insert(Over);
 
     commands.spawn_bundle(Text2dBundle {
-        text: Text::with_section(if won.0 { "You won!" } else { "You lost!" },
+        text: Text::with_section(match *run_outcome {
+                                     RunOutcome::Won => "You won!",
+                                     RunOutcome::Lost => "You lost!",
+                                     RunOutcome::Draw => "It's a draw!",
+                                 },
                                  text_styles.subtitle.clone(),
                                  TextAlignment {
                                      horizontal: HorizontalAlign::Center,
@@ -97,6 +110,20 @@ fn init(
         ..Default::default()
     }).insert(Over);
 
+    commands.spawn_bundle(Text2dBundle {
+        text: Text::with_section(format!("SEED {}", global_data.seed),
+                                 text_styles.love_bug_small.clone(),
+                                 TextAlignment {
+                                     horizontal: HorizontalAlign::Center,
+                                     ..Default::default()
+                                 }),
+        transform: Transform {
+            translation: Vec3::new(WIDTH / 2., HEIGHT / 4. - 40., 1.),
+            ..Default::default()
+        },
+        ..Default::default()
+    }).insert(Over);
+
     let player_data = player_data.single_mut().unwrap();
     let mut nb_cards = 0;
     for _ in player_data.board.iter() { nb_cards += 1; }
@@ -105,7 +132,7 @@ fn init(
         let slot = GameOverSlot { id: i as u8, total: nb_cards };
         let e = commands
             .spawn_bundle(SpriteBundle {
-                material: card.base_card.handle(&handles),
+                material: card.base_card.handle(&card_art),
                 transform: card_transform(slot.x(), slot.y()),
                 ..Default::default()
             })
@@ -7,8 +7,11 @@ use rand::rngs::StdRng;
 #[cfg(target_arch = "wasm32")]
 use bevy_webgl2;
 
-use crate::data::card::Card;
-use crate::fight::fight_screen::FightPlugin;
+use ld49_engine::combat::{AttackOrder, CombatRules, HalfBoard};
+use ld49_engine::replay::Replay;
+
+use crate::data::card::{Card, CardArt};
+use crate::fight::fight_screen::{FightPlugin, ReplayViewer};
 use crate::game_over::GameOverPlugin;
 use crate::data::loading::{AudioAssets, ColorAssets, TextureAssets};
 use crate::fight::predefined_hands::HandsName;
@@ -41,6 +44,9 @@ enum AppState {
 struct MainCamera;
 
 fn main() {
+    #[cfg(not(target_arch = "wasm32"))]
+    data::card::load_cards();
+
     let mut app = App::build();
 
     app.add_plugins(DefaultPlugins);
@@ -48,6 +54,10 @@ fn main() {
     #[cfg(target_arch = "wasm32")]
     app.add_plugin(bevy_webgl2::WebGL2Plugin);
 
+    if let Some(replay) = replay_from_args() {
+        app.insert_resource(ReplayViewer(replay));
+    }
+
     AssetLoader::new(AppState::Loading, AppState::Title)
         .with_collection::<TextureAssets>()
         .with_collection::<AudioAssets>()
@@ -62,6 +72,8 @@ fn main() {
             vsync: true,
             ..Default::default()
         })
+        .insert_resource(rules_from_args())
+        .init_resource::<CardArt>()
         .add_plugin(AudioPlugin)
         .add_plugin(ShopPlugin)
         .add_plugin(CardPlugin)
@@ -75,6 +87,38 @@ fn main() {
         .run();
 }
 
+/// Combat rules of the run, from `--left-to-right` and `--damage <formula>`.
+/// The turn of each fight is set by the fight.
+fn rules_from_args() -> CombatRules {
+    let mut rules = CombatRules::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--left-to-right" => rules.attack_order = AttackOrder::LeftToRight,
+            "--damage" => match args.next().map(|formula| formula.parse()) {
+                Some(Ok(damage)) => rules.damage = damage,
+                Some(Err(e)) => eprintln!("{}", e),
+                None => eprintln!("Missing damage formula"),
+            },
+            _ => {}
+        }
+    }
+    rules
+}
+
+/// Loads the file given with `--replay <file>`.
+fn replay_from_args() -> Option<Replay> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--replay").skip(1);
+    let path = args.next()?;
+    match Replay::load(&path) {
+        Ok(replay) => Some(replay),
+        Err(e) => {
+            eprintln!("Cannot load the replay {}: {}", path, e);
+            None
+        }
+    }
+}
+
 fn setup(
     mut commands: Commands,
     mut materials: ResMut<Assets<ColorMaterial>>,
@@ -108,6 +152,8 @@ pub struct PlayerData {
     extra_coins: u16, // For gold gained in fight
     hp: u16,
     shop_level: u16,
+    /// Turns since the shop was last upgraded, each one lowering the upgrade price by one coin
+    turns_since_upgrade: u16,
     ia: HandsName,
     // hero,
 }
@@ -123,23 +169,51 @@ impl Default for PlayerData {
             extra_coins: 0,
             hp: 25,
             shop_level: 1,
+            turns_since_upgrade: 0,
             ia: HandsName::Mush,
         }
     }
 }
 
+impl PlayerData {
+    pub fn half_board(&self) -> HalfBoard {
+        HalfBoard {
+            id: self.id,
+            hp: self.hp,
+            extra_coins: self.extra_coins,
+            shop_level: self.shop_level,
+            board: self.board.clone(),
+        }
+    }
+
+    pub fn set_half_board(&mut self, hb: HalfBoard) {
+        self.hp = hb.hp;
+        self.extra_coins = hb.extra_coins;
+        self.board = hb.board;
+    }
+}
+
+/// Every random roll of a run comes from `rng`, which is seeded with `seed`.
 pub struct GlobalData {
     rng: StdRng,
+    seed: u64,
     turn: u16,
     next_card_id: u32,
 }
 
-impl Default for GlobalData {
-    fn default() -> Self {
+impl GlobalData {
+    pub fn from_seed(seed: u64) -> Self {
         GlobalData {
-            rng: StdRng::from_entropy(),
+            rng: StdRng::seed_from_u64(seed),
+            seed,
             turn: 0,
             next_card_id: 0,
         }
     }
 }
+
+impl Default for GlobalData {
+    fn default() -> Self {
+        GlobalData::from_seed(rand::random())
+    }
+}
@@ -0,0 +1,194 @@
+use bevy::math::Vec4Swizzles;
+use bevy::prelude::*;
+use bevy_kira_audio::{Audio, AudioChannel};
+use rand::seq::IteratorRandom;
+use rand::rngs::StdRng;
+
+use crate::{GlobalData, HEIGHT, MainCamera, WIDTH};
+use crate::data::card::{BaseCards, Card, CARD_HEIGHT, CARD_SCALE, CARD_WIDTH, CardArt, CardHandle, Families};
+use crate::data::font::TextStyles;
+use crate::data::loading::{AudioAssets, ColorAssets};
+use crate::shop::shop_manager::CardPool;
+use crate::shop::shop_screen::{add_card, CheckTriples, HAND_SLOTS, ShopSlot, ShopSlots, StartDraggableAt};
+use crate::ui::card_overlay::NewCard;
+use crate::ui::drag_and_drop::Draggable;
+use crate::util::{cursor_pos, overlap, Z_ANNOUNCEMENT_BG, Z_DISCOVER};
+
+/// Number of cards to choose from
+const CHOICES: usize = 3;
+
+/// Spawned to let the player choose a card among three, which goes to their hand.
+/// Requests are presented one after the other, and skipped if the hand filled up in the meantime.
+pub struct Discover {
+    pub family: Option<Families>,
+    pub max_rank: Option<u8>,
+}
+
+impl Discover {
+    fn candidates(&self, rng: &mut StdRng) -> Vec<BaseCards> {
+        BaseCards::ALL.iter()
+            .filter(|base_card| self.family.map_or(true, |family| base_card.family() == family))
+            .filter(|base_card| self.max_rank.map_or(true, |rank| base_card.rank() <= rank))
+            .copied()
+            .choose_multiple(rng, CHOICES)
+    }
+}
+
+/// Entities of the discover overlay
+pub struct DiscoverUi;
+
+/// Background of the discover overlay, remembers if cards could be dragged before it was shown
+pub struct Discovering {
+    draggable: bool,
+}
+
+/// Discover requests and overlay: the turn can't end until they are gone
+pub(crate) type PendingDiscover = Or<(With<Discover>, With<Discovering>)>;
+
+pub struct DiscoverChoice(BaseCards);
+
+/// Ids of the hand slots left free by the cards in `slots`, from left to right
+pub(crate) fn free_hand_slots<'a>(slots: impl Iterator<Item=&'a ShopSlot>) -> Vec<u8> {
+    let occupied: Vec<u8> = slots.filter(|slot| slot.row == ShopSlots::HAND).map(|slot| slot.id).collect();
+    (0..HAND_SLOTS).filter(|id| !occupied.contains(id)).collect()
+}
+
+pub(crate) fn present_discover(
+    mut commands: Commands,
+    requests: Query<(Entity, &Discover)>,
+    discovering: Query<&Discovering>,
+    cards: Query<(Entity, &ShopSlot, Option<&Draggable>), With<Card>>,
+    mut global_data: ResMut<GlobalData>,
+    card_art: Res<CardArt>,
+    colors: Res<ColorAssets>,
+    text_styles: Res<TextStyles>,
+) {
+    if discovering.iter().next().is_some() {
+        return;
+    }
+    let (request, discover) = match requests.iter().next() {
+        Some(request) => request,
+        None => return,
+    };
+    commands.entity(request).despawn();
+
+    if free_hand_slots(cards.iter().map(|(_, slot, _)| slot)).is_empty() {
+        return;
+    }
+    let candidates = discover.candidates(&mut global_data.rng);
+    if candidates.is_empty() {
+        return;
+    }
+
+    // Cards can't be dragged until a card is chosen
+    let draggable = cards.iter().any(|(_, _, draggable)| draggable.is_some());
+    for (e, _, _) in cards.iter() {
+        commands.entity(e).remove::<Draggable>();
+    }
+
+    commands
+        .spawn_bundle(SpriteBundle {
+            material: colors.black.clone(),
+            sprite: Sprite::new(Vec2::new(WIDTH / 1.5, HEIGHT / 2.)),
+            transform: Transform::from_xyz(WIDTH / 2., HEIGHT / 2., Z_ANNOUNCEMENT_BG),
+            ..Default::default()
+        })
+        .insert(Discovering { draggable })
+        .insert(DiscoverUi);
+
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(
+                "Choose a card",
+                text_styles.note.clone(),
+                TextAlignment {
+                    horizontal: HorizontalAlign::Center,
+                    vertical: VerticalAlign::Center,
+                }),
+            transform: Transform::from_xyz(WIDTH / 2., HEIGHT / 2. + CARD_HEIGHT / 2. + 40., Z_DISCOVER),
+            ..Default::default()
+        })
+        .insert(DiscoverUi);
+
+    let middle = (candidates.len() - 1) as f32 / 2.;
+    for (i, &base_card) in candidates.iter().enumerate() {
+        let x = WIDTH / 2. + (i as f32 - middle) * 192.;
+        commands
+            .spawn_bundle(SpriteBundle {
+                material: base_card.handle(&card_art),
+                transform: Transform {
+                    translation: Vec3::new(x, HEIGHT / 2., Z_DISCOVER),
+                    scale: Vec3::new(CARD_SCALE, CARD_SCALE, 1.),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .insert(DiscoverChoice(base_card))
+            .insert(DiscoverUi);
+        commands
+            .spawn_bundle(Text2dBundle {
+                text: Text::with_section(
+                    base_card.name(),
+                    text_styles.note.clone(),
+                    TextAlignment {
+                        horizontal: HorizontalAlign::Center,
+                        vertical: VerticalAlign::Center,
+                    }),
+                transform: Transform::from_xyz(x, HEIGHT / 2. - CARD_HEIGHT / 2. - 30., Z_DISCOVER),
+                ..Default::default()
+            })
+            .insert(DiscoverUi);
+    }
+}
+
+pub(crate) fn choose_discover(
+    mut commands: Commands,
+    time: Res<Time>,
+    btn: Res<Input<MouseButton>>,
+    windows: Res<Windows>,
+    main_camera: Query<&Transform, With<MainCamera>>,
+    discovering: Query<&Discovering>,
+    choices: Query<(&Transform, &DiscoverChoice)>,
+    overlay: Query<Entity, With<DiscoverUi>>,
+    cards: Query<(Entity, &ShopSlot), With<Card>>,
+    (mut global_data, mut card_pool): (ResMut<GlobalData>, ResMut<CardPool>),
+    card_art: Res<CardArt>,
+    mut ev_new_card: EventWriter<NewCard>,
+    audio: Res<Audio>,
+    music: Res<AudioAssets>,
+) {
+    let draggable = match discovering.single() {
+        Ok(discovering) => discovering.draggable,
+        Err(_) => return,
+    };
+    if !btn.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let window = windows.get_primary().unwrap();
+    let cursor = match cursor_pos(window, main_camera.single().unwrap()) {
+        Some(cursor) => cursor,
+        None => return,
+    };
+    let chosen = choices.iter()
+        .find(|(transform, _)| overlap(cursor.xyz(), transform.translation, (CARD_WIDTH / 2., CARD_HEIGHT / 2.)))
+        .map(|(_, choice)| choice.0);
+    let base_card = match chosen {
+        Some(base_card) => base_card,
+        None => return,
+    };
+
+    for e in overlay.iter() {
+        commands.entity(e).despawn_recursive();
+    }
+    if let Some(&id) = free_hand_slots(cards.iter().map(|(_, slot)| slot)).first() {
+        audio.play_in_channel(music.place_card.clone(), &AudioChannel::new("SFX".to_owned()));
+        let card = Card::new(base_card, global_data.next_card_id);
+        global_data.next_card_id += 1;
+        card_pool.add_extra(&card);
+        add_card(card, ShopSlot { row: ShopSlots::HAND, id }, &mut commands, &card_art, &mut ev_new_card);
+        commands.spawn().insert(CheckTriples);
+    }
+    if draggable {
+        commands.spawn().insert(StartDraggableAt(time.seconds_since_startup()));
+    }
+}
@@ -1,3 +1,4 @@
+pub mod discover;
 pub mod shop_controls;
 pub mod shop_manager;
 pub mod shop_rules;
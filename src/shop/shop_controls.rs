@@ -3,9 +3,10 @@ use bevy::math::{vec2, Vec4Swizzles};
 use bevy::prelude::*;
 use bevy_kira_audio::{Audio, AudioChannel};
 use crate::{GlobalData, MainCamera, MySelf, PlayerData};
-use crate::data::card::{Card, CARD_HEIGHT, CARD_WIDTH};
-use crate::data::loading::{AudioAssets, TextureAssets};
-use crate::shop::shop_manager::ShopManager;
+use crate::data::card::{Card, CARD_HEIGHT, CARD_WIDTH, CardArt};
+use crate::data::loading::AudioAssets;
+use crate::shop::discover::PendingDiscover;
+use crate::shop::shop_manager::{CardPool, ShopConfig, ShopManager};
 use crate::shop::shop_screen::{add_card, ButtonText, FreezeButton, Hourglass, RefreshButton, ShopSlot, ShopSlots, ShopValues, ShopFrozen, StartFight, UpgradeButton};
 use crate::util::{cursor_pos, overlap};
 use crate::ui::drag_and_drop::Draggable;
@@ -15,7 +16,7 @@ pub(crate) fn handle_buttons(
     mut player_data: Query<&mut PlayerData, With<MySelf>>,
     btn: Res<Input<MouseButton>>,
     windows: Res<Windows>,
-    shop_values: Res<ShopValues>,
+    (shop_values, shop_config): (Res<ShopValues>, Res<ShopConfig>),
     main_camera: Query<&Transform, With<MainCamera>>,
     queries: QuerySet<(
         Query<&Transform, With<Hourglass>>,
@@ -27,35 +28,41 @@ pub(crate) fn handle_buttons(
     mut button_text: Query<&mut Text, With<ButtonText>>,
     mut frozen_shop: ResMut<ShopFrozen>,
     mut commands: Commands,
-    mut global_data: ResMut<GlobalData>,
-    handles: Res<TextureAssets>,
+    (mut global_data, mut card_pool): (ResMut<GlobalData>, ResMut<CardPool>),
+    card_art: Res<CardArt>,
     mut ev_new_card: EventWriter<NewCard>,
     mut ev_fight: EventWriter<StartFight>,
-    audio: Res<Audio>,
-    music: Res<AudioAssets>,
+    (audio, music): (Res<Audio>, Res<AudioAssets>),
+    pending_discover: Query<Entity, PendingDiscover>,
 ) {
     let window = windows.get_primary().unwrap();
     if let Some(cursor) = cursor_pos(window, main_camera.single().unwrap()) {
         let mut player_data = player_data.single_mut().unwrap();
+        let discovering = pending_discover.iter().next().is_some();
 
         let transform = queries.q1().single().unwrap();
         if overlap(cursor.xyz(), transform.translation, (50., 50.)) {
+            if discovering {
+                button_text.single_mut().unwrap().sections[0].value = "Choose a card first.".to_string();
+                return;
+            }
             button_text.single_mut().unwrap().sections[0].value = format!("Refresh cards for {} coins.", shop_values.refresh);
             if btn.just_pressed(MouseButton::Left) && player_data.coins >= shop_values.refresh {
                 audio.play_in_channel(music.refresh.clone(), &AudioChannel::new("SFX".to_owned()));
                 player_data.coins -= shop_values.refresh;
                 *frozen_shop = ShopFrozen(None);
-                for (e, _card, &slot) in card_query.iter() {
+                for (e, card, &slot) in card_query.iter() {
                     if slot.row == ShopSlots::SHOP {
                         commands.entity(e).despawn_recursive();
+                        card_pool.put_back(card);
                     }
                 }
-                for (i, &base_card) in ShopManager::shop_inventory(player_data.shop_level, &mut global_data.rng).iter().enumerate() {
+                for (i, &base_card) in ShopManager::shop_inventory(shop_config.level(player_data.shop_level), &mut card_pool, &mut global_data.rng).iter().enumerate() {
                     let id = global_data.next_card_id;
                     global_data.next_card_id += 1;
                     let card_id = add_card(Card::new(base_card, id),
                                            ShopSlot { row: ShopSlots::SHOP, id: i as u8 },
-                                           &mut commands, &handles, &mut ev_new_card);
+                                           &mut commands, &card_art, &mut ev_new_card);
                     commands
                         .entity(card_id)
                         .insert(Draggable { size: vec2(CARD_WIDTH / 2., CARD_HEIGHT / 2.) });
@@ -89,28 +96,39 @@ pub(crate) fn handle_buttons(
 
         let transform = queries.q3().single().unwrap();
         if overlap(cursor.xyz(), transform.translation, (50., 50.)) {
-            let upgrade_cost: i16 = match player_data.shop_level {
-                1 => 4,
-                2 => 6,
-                3 => 8,
-                _ => -1,
-            };
-            if upgrade_cost == -1 {
-                button_text.single_mut().unwrap().sections[0].value = "The shop can't be upgraded anymore.".to_string();
+            if discovering {
+                button_text.single_mut().unwrap().sections[0].value = "Choose a card first.".to_string();
                 return;
-            } else {
-                button_text.single_mut().unwrap().sections[0].value = format!("Upgrade the shop for {} coins.", upgrade_cost);
-                if btn.just_pressed(MouseButton::Left) && player_data.coins >= upgrade_cost as u16 {
-                    audio.play_in_channel(music.level_up.clone(), &AudioChannel::new("SFX".to_owned()));
-                    player_data.coins -= upgrade_cost as u16;
-                    player_data.shop_level += 1;
+            }
+            match shop_config.upgrade_cost(player_data.shop_level, player_data.turns_since_upgrade) {
+                None => {
+                    button_text.single_mut().unwrap().sections[0].value = "The shop can't be upgraded anymore.".to_string();
+                    return;
+                }
+                Some(upgrade_cost) => {
+                    // Turns past the base price take no more coins off
+                    let base_cost = shop_config.upgrade_cost(player_data.shop_level, 0).unwrap_or(upgrade_cost);
+                    button_text.single_mut().unwrap().sections[0].value = match base_cost - upgrade_cost {
+                        0 => format!("Upgrade the shop for {} coins.", upgrade_cost),
+                        discount => format!("Upgrade the shop for {} coins ({} off).", upgrade_cost, discount),
+                    };
+                    if btn.just_pressed(MouseButton::Left) && player_data.coins >= upgrade_cost {
+                        audio.play_in_channel(music.level_up.clone(), &AudioChannel::new("SFX".to_owned()));
+                        player_data.coins -= upgrade_cost;
+                        player_data.shop_level += 1;
+                        player_data.turns_since_upgrade = 0;
+                    }
+                    return;
                 }
-                return;
             }
         }
 
         let transform = queries.q0().single().unwrap();
         if overlap(cursor.xyz(), transform.translation, (60., 70.)) {
+            if discovering {
+                button_text.single_mut().unwrap().sections[0].value = "Choose a card first.".to_string();
+                return;
+            }
             button_text.single_mut().unwrap().sections[0].value = "Click to end your turn.".to_string();
             if btn.just_pressed(MouseButton::Left) {
                 ev_fight.send(StartFight);
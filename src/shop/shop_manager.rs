@@ -1,94 +1,203 @@
+use std::cmp::min;
+use std::collections::HashMap;
+
 use rand::Rng;
 use rand::rngs::StdRng;
-use crate::data::card::BaseCards;
+use serde::Deserialize;
+
+use crate::data::card::{BaseCards, Card};
 
 pub struct ShopManager;
 
-impl ShopManager {
-    fn draw_level1(rng: &mut StdRng) -> BaseCards {
-        match rng.gen_range(0..8) {
-            0 => BaseCards::Merch1,
-            1 => BaseCards::Merch2,
-            2 => BaseCards::Mush1,
-            3 => BaseCards::Mush2,
-            4 => BaseCards::Rob1,
-            5 => BaseCards::Rob2,
-            6 => BaseCards::Spid1,
-            _ => BaseCards::Spid2,
+/// Copies of each card in the pool at the beginning of a run
+fn copies_per_rank(rank: u8) -> u16 {
+    match rank {
+        1 => 16,
+        2 => 14,
+        3 => 12,
+        4 => 10,
+        5 => 8,
+        _ => 6,
+    }
+}
+
+/// Cards shared by every shop of a run.
+/// Cards drawn in a shop leave the pool, they come back when the shop is refreshed
+/// or when they are sold or destroyed. Cards summoned by abilities or discovered don't come from the pool
+/// and don't go back to it, so a card never has more copies in the pool than at the beginning of the run.
+pub struct CardPool {
+    left: HashMap<BaseCards, u16>,
+    /// Copies held by each card that didn't come from the pool
+    extra_copies: HashMap<u32, u16>,
+}
+
+impl Default for CardPool {
+    fn default() -> Self {
+        CardPool {
+            left: BaseCards::ALL.iter()
+                .map(|&base_card| (base_card, copies_per_rank(base_card.rank())))
+                .collect(),
+            extra_copies: HashMap::new(),
         }
     }
+}
+
+/// A golden card being three copies
+fn copies(card: &Card) -> u16 {
+    if card.golden { 3 } else { 1 }
+}
 
-    fn draw_level2(rng: &mut StdRng) -> BaseCards {
-        match rng.gen_range(0..8) {
-            0 => BaseCards::Merch3,
-            1 => BaseCards::Merch4,
-            2 => BaseCards::Mush3,
-            3 => BaseCards::Mush4,
-            4 => BaseCards::Rob3,
-            5 => BaseCards::Rob4,
-            6 => BaseCards::Spid3,
-            _ => BaseCards::Spid4,
+impl CardPool {
+    /// Copies of `base_card` left in the pool
+    pub fn left(&self, base_card: BaseCards) -> u16 {
+        *self.left.get(&base_card).unwrap_or(&0)
+    }
+
+    /// Records a card that didn't come from the pool, e.g. summoned by an ability
+    pub fn add_extra(&mut self, card: &Card) {
+        self.extra_copies.insert(card.id, copies(card));
+    }
+
+    /// The golden card made of `merged` holds their copies that didn't come from the pool
+    pub fn merge(&mut self, merged: &[Card], golden: &Card) {
+        let extra: u16 = merged.iter().filter_map(|card| self.extra_copies.remove(&card.id)).sum();
+        if extra > 0 {
+            self.extra_copies.insert(golden.id, extra);
         }
     }
 
-    fn draw_level3(rng: &mut StdRng) -> BaseCards {
-        match rng.gen_range(0..8) {
-            0 => BaseCards::Merch5,
-            1 => BaseCards::Merch6,
-            2 => BaseCards::Mush5,
-            3 => BaseCards::Mush6,
-            4 => BaseCards::Rob5,
-            5 => BaseCards::Rob6,
-            6 => BaseCards::Spid5,
-            _ => BaseCards::Spid6,
+    /// Takes a card whose rank is in `ranks`, each copy left having the same odds
+    fn take(&mut self, ranks: &[u8], rng: &mut StdRng) -> Option<BaseCards> {
+        let candidates: Vec<(BaseCards, u16)> = BaseCards::ALL.iter()
+            .filter(|base_card| ranks.contains(&base_card.rank()))
+            .map(|&base_card| (base_card, self.left(base_card)))
+            .filter(|&(_, left)| left > 0)
+            .collect();
+        let total: u16 = candidates.iter().map(|&(_, left)| left).sum();
+        if total == 0 {
+            return None;
         }
+
+        let mut roll = rng.gen_range(0..total);
+        for (base_card, left) in candidates {
+            if roll < left {
+                *self.left.get_mut(&base_card).unwrap() -= 1;
+                return Some(base_card);
+            }
+            roll -= left;
+        }
+        None
     }
 
-    fn draw_level4(rng: &mut StdRng) -> BaseCards {
-        match rng.gen_range(0..8) {
-            0 => BaseCards::Merch7,
-            1 => BaseCards::Merch8,
-            2 => BaseCards::Mush7,
-            3 => BaseCards::Mush8,
-            4 => BaseCards::Rob7,
-            5 => BaseCards::Rob8,
-            6 => BaseCards::Spid7,
-            _ => BaseCards::Spid8,
+    /// Returns the copies of a card that came from the pool
+    pub fn put_back(&mut self, card: &Card) {
+        let extra = self.extra_copies.remove(&card.id).unwrap_or(0);
+        let max_copies = copies_per_rank(card.base_card.rank());
+        let left = self.left.entry(card.base_card).or_insert(0);
+        *left = min(*left + copies(card).saturating_sub(extra), max_copies);
+    }
+}
+
+/// Where the shop levels are read from when the game starts
+pub const SHOP_FILE: &str = "assets/shop.ron";
+
+/// Number of slots of the shop row
+pub const SHOP_SLOTS: u16 = 6;
+
+const DEFAULT_SHOP: &str = include_str!("../../assets/shop.ron");
+
+/// Shop levels, the first one being the shop level 1.
+#[derive(Deserialize)]
+pub struct ShopConfig {
+    pub levels: Vec<ShopLevel>,
+}
+
+#[derive(Deserialize)]
+pub struct ShopLevel {
+    /// Number of cards offered
+    pub cards: u16,
+    /// Weight of each rank, from rank 1
+    pub odds: Vec<u16>,
+    /// Price of upgrading the shop from the previous level
+    pub upgrade_cost: u16,
+}
+
+impl ShopConfig {
+    pub fn from_ron(ron: &str) -> Result<Self, String> {
+        let config: ShopConfig = ron::from_str(ron).map_err(|e| e.to_string())?;
+        if config.levels.is_empty() {
+            return Err("There should be at least one shop level".to_string());
+        }
+        for (i, level) in config.levels.iter().enumerate() {
+            if level.odds.iter().sum::<u16>() == 0 {
+                return Err(format!("Shop level {} can't draw any rank", i + 1));
+            }
+            if level.cards > SHOP_SLOTS {
+                return Err(format!("Shop level {} offers more than {} cards", i + 1, SHOP_SLOTS));
+            }
         }
+        Ok(config)
+    }
+
+    /// Reads `SHOP_FILE` if it can be read, the built-in shop levels otherwise.
+    pub fn load() -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Ok(ron) = std::fs::read_to_string(SHOP_FILE) {
+            return ShopConfig::from_ron(&ron).unwrap_or_else(|e| panic!("Invalid {}: {}", SHOP_FILE, e));
+        }
+        ShopConfig::from_ron(DEFAULT_SHOP).expect("Invalid built-in shop levels")
+    }
+
+    pub fn max_level(&self) -> u16 {
+        self.levels.len() as u16
+    }
+
+    /// Levels above the last one use the last one
+    pub fn level(&self, shop_level: u16) -> &ShopLevel {
+        let index = min(shop_level.max(1), self.max_level()) - 1;
+        &self.levels[index as usize]
+    }
+
+    /// Price of upgrading from `shop_level` after `turns` turns without upgrading, if it isn't the last level
+    pub fn upgrade_cost(&self, shop_level: u16, turns: u16) -> Option<u16> {
+        self.levels.get(shop_level as usize).map(|level| level.upgrade_cost.saturating_sub(turns))
     }
+}
 
-    fn cards_amount(level: u16) -> u16 {
-        match level {
-            1 => 3,
-            2 => 4,
-            3 => 5,
-            _ => 6,
+impl ShopLevel {
+    /// Odds of drawing each rank, from rank 1
+    pub fn rank_percents(&self) -> Vec<f32> {
+        let total: u16 = self.odds.iter().sum();
+        self.odds.iter().map(|&weight| 100. * weight as f32 / total as f32).collect()
+    }
+
+    fn draw_rank(&self, rng: &mut StdRng) -> u8 {
+        let total: u16 = self.odds.iter().sum();
+        let mut roll = rng.gen_range(0..total);
+        for (i, &weight) in self.odds.iter().enumerate() {
+            if roll < weight {
+                return i as u8 + 1;
+            }
+            roll -= weight;
         }
+        self.odds.len() as u8
     }
+}
 
-    pub fn shop_inventory(level: u16, rng: &mut StdRng) -> Vec<BaseCards> {
+impl ShopManager {
+    /// Draws the cards of a shop from `pool`.
+    /// When no copy of the drawn rank is left, the card is drawn from every rank of the shop level,
+    /// and the shop has less cards if the pool runs out.
+    pub fn shop_inventory(level: &ShopLevel, pool: &mut CardPool, rng: &mut StdRng) -> Vec<BaseCards> {
+        let all_ranks: Vec<u8> = (1..=level.odds.len() as u8).collect();
         let mut cards = vec![];
-        for _ in 0..ShopManager::cards_amount(level) {
-            match level {
-                1 => cards.push(ShopManager::draw_level1(rng)),
-                2 => match rng.gen_range(0..2) {
-                    0 => cards.push(ShopManager::draw_level1(rng)),
-                    _ => cards.push(ShopManager::draw_level2(rng)),
-                }
-                3 => match rng.gen_range(0..5) {
-                    0 => cards.push(ShopManager::draw_level1(rng)),
-                    1|2 => cards.push(ShopManager::draw_level2(rng)),
-                    _ => cards.push(ShopManager::draw_level3(rng)),
-                }
-                _ => match rng.gen_range(0..11) {
-                    0|1 => cards.push(ShopManager::draw_level1(rng)),
-                    2|3|4 => cards.push(ShopManager::draw_level2(rng)),
-                    5|6|7|8 => cards.push(ShopManager::draw_level3(rng)),
-                    _ => cards.push(ShopManager::draw_level4(rng)),
-                }
+        for _ in 0..level.cards {
+            let rank = level.draw_rank(rng);
+            if let Some(card) = pool.take(&[rank], rng).or_else(|| pool.take(&all_ranks, rng)) {
+                cards.push(card);
             }
         }
         return cards;
     }
 
-}
\ No newline at end of file
+}
@@ -77,7 +77,7 @@ impl ShopRules {
         let mut rule: ShopRules = rng.sample(Standard);
         if turn == 1 {
             // Prevent not being able to buy cards on first turn
-            while rule == ShopRules::BuyPlus { rule = rand::random(); }
+            while rule == ShopRules::BuyPlus { rule = rng.sample(Standard); }
         }
         rule.edit_values(values);
         return rule.description();
@@ -14,9 +14,10 @@ use crate::data::loading::{AudioAssets, ColorAssets};
 use crate::data::loading::TextureAssets;
 use crate::fight::fight_screen::MyFoe;
 use crate::GlobalData;
-use crate::shop::shop_manager::ShopManager;
+use crate::shop::shop_manager::{CardPool, SHOP_SLOTS, ShopConfig, ShopManager};
 use crate::shop::shop_rules::ShopRules;
 use crate::shop::shop_controls::handle_buttons;
+use crate::shop::discover::{choose_discover, Discover, DiscoverUi, free_hand_slots, PendingDiscover, present_discover};
 use crate::ui::StateBackground;
 use crate::ui::card_overlay::{NewCard, StatsChanged};
 use crate::ui::drag_and_drop::{Draggable, Dragged, DROP_BORDER, Dropped};
@@ -34,7 +35,10 @@ pub enum ShopSlots {
     SELL,
 }
 
-#[derive(PartialEq, Copy, Clone)]
+/// Number of slots of the hand row
+pub(crate) const HAND_SLOTS: u8 = 5;
+
+#[derive(PartialEq, Copy, Clone, Debug)]
 pub(crate) struct ShopSlot {
     pub row: ShopSlots,
     pub id: u8,
@@ -97,6 +101,9 @@ struct SoldTrigger(Card);
 
 pub(crate) struct StartFight;
 
+/// Spawned when cards are bought or summoned: three copies of a card are merged once they are there
+pub(crate) struct CheckTriples;
+
 const MIN_COINS: u16 = 3;
 
 pub struct ShopValues {
@@ -129,6 +136,9 @@ impl Plugin for ShopPlugin {
             .add_event::<SoldTrigger>()
             .add_event::<StartFight>()
             .insert_resource(ShopFrozen(None))
+            .init_resource::<CardPool>()
+            .insert_resource(ShopConfig::load())
+            .insert_resource(ShowShopOdds(false))
             .insert_resource(CanRefresh(false))
             .add_system_set(
                 SystemSet::on_enter(AppState::Shop)
@@ -148,6 +158,10 @@ impl Plugin for ShopPlugin {
                     .with_system(played_trigger.system())
                     .with_system(sold_trigger.system())
                     .with_system(start_fight.system())
+                    .with_system(merge_triples.system())
+                    .with_system(shop_odds_overlay.system())
+                    .with_system(present_discover.system())
+                    .with_system(choose_discover.system())
             )
             .add_system_set(
                 SystemSet::on_exit(AppState::Shop)
@@ -161,6 +175,8 @@ impl Plugin for ShopPlugin {
                     .with_system(cleanup_system::<ShopSlot>.system())
                     .with_system(cleanup_system::<SlotBorder>.system())
                     .with_system(cleanup_system::<ShopUi>.system())
+                    .with_system(cleanup_system::<Discover>.system())
+                    .with_system(cleanup_system::<DiscoverUi>.system())
                     .with_system(cleanup_system::<ShopValues>.system())
                     .with_system(cleanup_system::<CoinLimit>.system())
                     .with_system(cleanup_system::<Level>.system())
@@ -177,6 +193,11 @@ pub(crate) struct ShopFrozen(pub Option<Vec<(u8, Card)>>);
 
 struct CanRefresh(bool);
 
+/// Toggled with F3 to display the odds of the current shop level
+struct ShowShopOdds(bool);
+
+struct ShopOddsOverlay;
+
 const SHOP_RULE_POPUP_DURATION: f64 = 6.;
 
 
@@ -186,18 +207,24 @@ fn init(
     mut global_data: ResMut<GlobalData>,
     mut ev_new_card: EventWriter<NewCard>,
     handles: Res<TextureAssets>,
+    card_art: Res<CardArt>,
     text_styles: Res<TextStyles>,
     colors: Res<ColorAssets>,
     audio: Res<Audio>,
     songs: Res<AudioAssets>,
     mut query: Query<&mut PlayerData, With<MySelf>>,
     mut frozen_shop: ResMut<ShopFrozen>,
+    mut card_pool: ResMut<CardPool>,
+    shop_config: Res<ShopConfig>,
 ) {
     let mut player_data = query.single_mut().expect(
         "There should be one and only one player with myself"
     );
 
     global_data.turn += 1;
+    if global_data.turn > 1 {
+        player_data.turns_since_upgrade += 1;
+    }
 
     audio.stop();
     audio.play_looped_with_intro(songs.intro.clone(), songs.shop.clone());
@@ -255,13 +282,13 @@ fn init(
     for (i, &card) in player_data.board.iter().enumerate() {
         add_card(card,
                  ShopSlot { row: ShopSlots::BOARD, id: i as u8 },
-                 &mut commands, &handles, &mut ev_new_card);
+                 &mut commands, &card_art, &mut ev_new_card);
     }
 
     for (i, &card) in player_data.hand.iter().enumerate() {
         add_card(card,
                  ShopSlot { row: ShopSlots::HAND, id: i as u8 },
-                 &mut commands, &handles, &mut ev_new_card);
+                 &mut commands, &card_art, &mut ev_new_card);
     }
 
     let to_display_in_shop =
@@ -270,7 +297,7 @@ fn init(
             *frozen_shop = ShopFrozen(None);
             v
         } else {
-            ShopManager::shop_inventory(player_data.shop_level, &mut global_data.rng)
+            ShopManager::shop_inventory(shop_config.level(player_data.shop_level), &mut card_pool, &mut global_data.rng)
                 .iter().enumerate().map(|(i, &base_card)| {
                 let id = global_data.next_card_id;
                 global_data.next_card_id += 1;
@@ -280,7 +307,7 @@ fn init(
     for (i, card) in to_display_in_shop {
         add_card(card,
                  ShopSlot { row: ShopSlots::SHOP, id: i as u8 },
-                 &mut commands, &handles, &mut ev_new_card);
+                 &mut commands, &card_art, &mut ev_new_card);
     }
 
     let bob_slot = ShopSlot { row: ShopSlots::SELL, id: 0 };
@@ -300,10 +327,10 @@ fn init(
     // Slots
     commands.spawn().insert(bob_slot);
     for i in 0..=6 {
-        if i <= 4 {
+        if i < HAND_SLOTS {
             commands.spawn().insert(ShopSlot { row: ShopSlots::HAND, id: i });
         }
-        if i < 6 {
+        if (i as u16) < SHOP_SLOTS {
             commands.spawn().insert(ShopSlot { row: ShopSlots::SHOP, id: i });
         }
         commands.spawn().insert(ShopSlot { row: ShopSlots::BOARD, id: i });
@@ -362,6 +389,17 @@ fn init(
         )
         .insert(Level);
 
+    commands
+        .spawn_bundle(
+            text_bundle_at_corner(
+                Corners::TopRight,
+                vec!["".to_string()],
+                &text_styles.love_bug_small,
+            )
+        )
+        .insert(ShopOddsOverlay)
+        .insert(ShopUi);
+
     commands
         .spawn_bundle(SpriteBundle {
             material: handles.refresh_button.clone(),
@@ -476,7 +514,8 @@ fn played_trigger(
     mut ev_stats: EventWriter<StatsChanged>,
     mut ev_new_card: EventWriter<NewCard>,
     handles: Res<TextureAssets>,
-    mut global_data: ResMut<GlobalData>,
+    card_art: Res<CardArt>,
+    (mut global_data, mut card_pool): (ResMut<GlobalData>, ResMut<CardPool>),
     mut cards: QuerySet<(
         Query<(&mut Card, &ShopSlot)>,
         Query<(Entity, &mut Card, &ShopSlot)>
@@ -504,13 +543,14 @@ fn played_trigger(
 
         let (card, _slot) = cards.q0_mut().get_mut(trigger.0).unwrap();
         let ability = card.base_card.ability();
+        let magnitude = card.magnitude();
 
         match ability {
             Abilities::SweetScent => {
                 for (e, mut card, other_slot) in cards.q1_mut().iter_mut() {
                     if other_slot.row != ShopSlots::BOARD || e == trigger.0 || card.base_card.family() != Families::Mushrooms { continue; }
-                    card.atk += 1;
-                    card.hp += 1;
+                    card.atk += magnitude;
+                    card.hp += magnitude;
                     ev_stats.send(StatsChanged(e));
                 }
             }
@@ -521,7 +561,7 @@ fn played_trigger(
                     other_spiders += 1;
                 }
                 let (mut card, _) = cards.q0_mut().get_mut(trigger.0).unwrap();
-                card.hp += other_spiders;
+                card.hp += other_spiders * magnitude;
                 ev_stats.send(StatsChanged(trigger.0));
             }
             Abilities::Replication => {
@@ -530,18 +570,18 @@ fn played_trigger(
                     if other_slot.row != ShopSlots::BOARD { continue; }
                     occupied_slots.insert(other_slot.id);
                 }
-                for i in 0..=6 {
-                    if !occupied_slots.contains(&i) {
-                        let nanobot = add_card(Card::new(BaseCards::Rob1, global_data.next_card_id),
-                                               ShopSlot { row: ShopSlots::BOARD, id: i as u8 },
-                                               &mut commands, &handles, &mut ev_new_card);
-                        commands
-                            .entity(nanobot)
-                            .insert(Draggable { size: vec2(CARD_WIDTH / 2., CARD_HEIGHT / 2.) });
-                        global_data.next_card_id += 1;
-                        break;
-                    }
+                for i in (0..=6).filter(|i| !occupied_slots.contains(i)).take(magnitude as usize) {
+                    let card = Card::new(BaseCards::Rob1, global_data.next_card_id);
+                    global_data.next_card_id += 1;
+                    card_pool.add_extra(&card);
+                    let nanobot = add_card(card,
+                                           ShopSlot { row: ShopSlots::BOARD, id: i as u8 },
+                                           &mut commands, &card_art, &mut ev_new_card);
+                    commands
+                        .entity(nanobot)
+                        .insert(Draggable { size: vec2(CARD_WIDTH / 2., CARD_HEIGHT / 2.) });
                 }
+                commands.spawn().insert(CheckTriples);
             }
             Abilities::Scanner => {
                 let mut other_robots = 0;
@@ -550,8 +590,8 @@ fn played_trigger(
                     other_robots += 1;
                 }
                 let (mut card, _) = cards.q0_mut().get_mut(trigger.0).unwrap();
-                card.hp += other_robots;
-                card.atk += other_robots;
+                card.hp += other_robots * magnitude;
+                card.atk += other_robots * magnitude;
                 ev_stats.send(StatsChanged(trigger.0));
             }
             _ => {}
@@ -564,16 +604,17 @@ fn sold_trigger(
     mut commands: Commands,
     mut ev_sold: EventReader<SoldTrigger>,
     mut ev_stats: EventWriter<StatsChanged>,
-    mut ev_new_card: EventWriter<NewCard>,
     handles: Res<TextureAssets>,
     mut global_data: ResMut<GlobalData>,
     mut cards: Query<(Entity, &mut Card, &ShopSlot)>,
     bob: Query<Entity, With<Bob>>,
+    player_data: Query<&PlayerData, With<MySelf>>,
     audio: Res<Audio>,
     music: Res<AudioAssets>,
 ) {
     for trigger in ev_sold.iter() {
         let ability = trigger.0.base_card.ability();
+        let magnitude = trigger.0.magnitude();
         let triggered = trigger.0.base_card.trigger() == Triggers::Sold;
 
         if triggered {
@@ -597,23 +638,14 @@ fn sold_trigger(
 
         match ability {
             Abilities::Sporocarp => {
-                let mut occupied_slots = HashSet::new();
-                for (_, _, other_slot) in cards.iter_mut() {
-                    if other_slot.row != ShopSlots::HAND { continue; }
-                    occupied_slots.insert(other_slot.id);
-                }
-                for i in 0..=4 {
-                    if !occupied_slots.contains(&i) {
-                        let random_mush = BaseCards::random_mush(&mut global_data.rng);
-                        let mush = add_card(Card::new(random_mush, global_data.next_card_id),
-                                                   ShopSlot { row: ShopSlots::HAND, id: i as u8 },
-                                                   &mut commands, &handles, &mut ev_new_card);
-                        commands
-                            .entity(mush)
-                            .insert(Draggable { size: vec2(CARD_WIDTH / 2., CARD_HEIGHT / 2.) });
-                        global_data.next_card_id += 1;
-                        break;
-                    }
+                let shop_level = player_data.single().expect("No data for the player").shop_level;
+                // No card to choose when the hand is full
+                let free_slots = free_hand_slots(cards.iter_mut().map(|(_, _, slot)| slot)).len();
+                for _ in 0..min(magnitude as usize, free_slots) {
+                    commands.spawn().insert(Discover {
+                        family: Some(Families::Mushrooms),
+                        max_rank: Some(shop_level as u8),
+                    });
                 }
             }
             Abilities::Altruism => {
@@ -653,12 +685,14 @@ fn display_ability_animation(
     mut card_query: Query<(Entity, &ShopSlot, &mut Card)>,
     mut commands: Commands,
     handles: Res<TextureAssets>,
+    card_art: Res<CardArt>,
     mut player_query: Query<&mut PlayerData, With<MySelf>>,
     mut global_data: ResMut<GlobalData>,
     mut ev_new_card: EventWriter<NewCard>,
     mut ev_stats: EventWriter<StatsChanged>,
     mut ev_gold_event: EventWriter<CoinsDiff>,
     timer: Query<Entity, With<ShopTimer>>,
+    mut card_pool: ResMut<CardPool>,
     audio: Res<Audio>,
     music: Res<AudioAssets>,
 ) {
@@ -667,9 +701,11 @@ fn display_ability_animation(
         if ab_stack.next_tick_after < t {
             if let Some((ability, card_id)) = ab_stack.stack.pop() {
                 let mut slot = None;
+                let mut magnitude = 1;
                 for (_, &s, c) in card_query.iter_mut() {
                     if c.id == card_id {
                         slot = Some(s);
+                        magnitude = c.magnitude();
                     }
                 }
 
@@ -689,14 +725,18 @@ fn display_ability_animation(
                     match ability {
                         Abilities::Spawn => {
                             let mut player_data = player_query.single_mut().expect("There should be a main player.");
-                            if player_data.board.len() < 7 {
-                                let base_card = if global_data.rng.gen() { BaseCards::Spid1 } else { BaseCards::Spid2 };
-                                let new_card = Card::new(base_card, global_data.next_card_id);
-                                global_data.next_card_id += 1;
-                                player_data.board.push(new_card);
-                                let new_slot = ShopSlot { row: ShopSlots::BOARD, id: player_data.board.len() as u8 - 1 };
-                                add_card(new_card, new_slot, &mut commands, &handles, &mut ev_new_card);
+                            for _ in 0..magnitude {
+                                if player_data.board.len() < 7 {
+                                    let base_card = if global_data.rng.gen() { BaseCards::Spid1 } else { BaseCards::Spid2 };
+                                    let new_card = Card::new(base_card, global_data.next_card_id);
+                                    global_data.next_card_id += 1;
+                                    card_pool.add_extra(&new_card);
+                                    player_data.board.push(new_card);
+                                    let new_slot = ShopSlot { row: ShopSlots::BOARD, id: player_data.board.len() as u8 - 1 };
+                                    add_card(new_card, new_slot, &mut commands, &card_art, &mut ev_new_card);
+                                }
                             }
+                            commands.spawn().insert(CheckTriples);
                         }
                         Abilities::Cannibalism => {
                             if let Some((eaten_entity, _, eaten_card)) = card_query.iter_mut()
@@ -707,6 +747,7 @@ fn display_ability_animation(
                                 .min_by_key(|(_, _, card)| card.base_card.rank()) {
                                 commands.entity(eaten_entity)
                                     .despawn_recursive();
+                                card_pool.put_back(&eaten_card);
                                 let change_hp = eaten_card.hp;
                                 let change_atk = eaten_card.atk;
                                 for (e, _, mut c) in card_query.iter_mut() {
@@ -724,8 +765,8 @@ fn display_ability_animation(
                             for (e, &s, mut card) in card_query.iter_mut() {
                                 if s.row == ShopSlots::BOARD
                                     && (s.id == index + 1 || s.id + 1 == index || s.id == index) {
-                                    card.hp += 1;
-                                    card.atk += 1;
+                                    card.hp += magnitude;
+                                    card.atk += magnitude;
                                     ev_stats.send(StatsChanged(e));
                                 }
                             }
@@ -736,12 +777,13 @@ fn display_ability_animation(
                                     if card.hp < 3 {
                                         commands.entity(e)
                                             .despawn_recursive();
+                                        card_pool.put_back(&card);
                                     } else if s.row == ShopSlots::BOARD {
                                         card.hp -= 2;
                                         ev_stats.send(StatsChanged(e))
                                     }
                                 } else if card.base_card.family() == Families::Robots {
-                                    card.hp += 2;
+                                    card.hp += 2 * magnitude;
                                     ev_stats.send(StatsChanged(e));
                                 }
                             }
@@ -751,13 +793,15 @@ fn display_ability_animation(
                             let mut change_atk = 0u16;
                             for (e, &s, mut card) in card_query.iter_mut() {
                                 if card.base_card.family() == Families::Robots && s.row == ShopSlots::BOARD && card.id != card_id {
-                                    change_hp += 1;
-                                    let atk = min(1, card.atk);
+                                    let hp = min(magnitude, card.hp);
+                                    change_hp += hp;
+                                    let atk = min(magnitude, card.atk);
                                     change_atk += atk;
                                     card.atk -= atk;
-                                    card.hp -= 1;
+                                    card.hp -= hp;
                                     if card.hp < 1 {
-                                        commands.entity(e).despawn_recursive()
+                                        commands.entity(e).despawn_recursive();
+                                        card_pool.put_back(&card);
                                     } else {
                                         ev_stats.send(StatsChanged(e));
                                     }
@@ -774,7 +818,7 @@ fn display_ability_animation(
                         Abilities::Slimy => {
                             for (e, _, mut card) in card_query.iter_mut() {
                                 if card.id == card_id {
-                                    card.hp += 1;
+                                    card.hp += magnitude;
                                     ev_stats.send(StatsChanged(e));
                                 }
                             }
@@ -788,13 +832,13 @@ fn display_ability_animation(
                             }
                             for (e, _, mut card) in card_query.iter_mut() {
                                 if card.id == card_id {
-                                    card.hp += num as u16;
+                                    card.hp += num * magnitude;
                                     ev_stats.send(StatsChanged(e));
                                 }
                             }
                         }
                         Abilities::GoldMine => {
-                            ev_gold_event.send(CoinsDiff(-1, true));
+                            ev_gold_event.send(CoinsDiff(-(magnitude as i8), true));
                         }
                         _ => {}
                     }
@@ -812,16 +856,21 @@ fn display_ability_animation(
     }
 }
 
-pub(crate) fn add_card(card: Card, slot: ShopSlot, commands: &mut Commands, handles: &Res<TextureAssets>, ev_new_card: &mut EventWriter<NewCard>) -> Entity {
+pub(crate) fn add_card(card: Card, slot: ShopSlot, commands: &mut Commands, card_art: &Res<CardArt>, ev_new_card: &mut EventWriter<NewCard>) -> Entity {
     let id = commands
         .spawn_bundle(SpriteBundle {
-            material: card.base_card.handle(&handles),
+            material: card.base_card.handle(card_art),
             transform: card_transform(slot.x(), slot.y()),
             ..Default::default()
         })
         .insert(card)
         .insert(slot)
         .id();
+    if card.golden {
+        commands.entity(id).with_children(|parent| {
+            parent.spawn_bundle(card_art.golden_frame());
+        });
+    }
     ev_new_card.send(NewCard(id, card.clone()));
     return id;
 }
@@ -874,12 +923,56 @@ fn update_ui(
     }
 }
 
+fn shop_odds_overlay(
+    keys: Res<Input<KeyCode>>,
+    mut show_odds: ResMut<ShowShopOdds>,
+    shop_config: Res<ShopConfig>,
+    card_pool: Res<CardPool>,
+    player_data: Query<&PlayerData, With<MySelf>>,
+    mut overlay: Query<&mut Text, With<ShopOddsOverlay>>,
+) {
+    if keys.just_pressed(KeyCode::F3) {
+        show_odds.0 = !show_odds.0;
+    }
+    let mut text = match overlay.single_mut() {
+        Ok(text) => text,
+        Err(_) => return,
+    };
+    if !show_odds.0 {
+        text.sections[0].value = "".to_string();
+        return;
+    }
+
+    let shop_level = player_data.single().expect("No data for the player").shop_level;
+    let level = shop_config.level(shop_level);
+    let mut value = format!("SHOP LEVEL {}: {} CARDS\n", shop_level, level.cards);
+    for (i, percent) in level.rank_percents().iter().enumerate() {
+        let rank = i as u8 + 1;
+        let left: u16 = BaseCards::ALL.iter()
+            .filter(|base_card| base_card.rank() == rank)
+            .map(|&base_card| card_pool.left(base_card))
+            .sum();
+        value.push_str(&format!("RANK {}: {:.0}% ({} LEFT)\n", rank, percent, left));
+    }
+    let data = player_data.single().expect("No data for the player");
+    value.push_str(&match shop_config.upgrade_cost(data.shop_level, data.turns_since_upgrade) {
+        Some(cost) => format!("UPGRADE: {} COINS", cost),
+        None => "MAX LEVEL".to_string(),
+    });
+    text.sections[0].value = value;
+}
+
 fn start_fight(
     mut ev_fight: EventReader<StartFight>,
     mut state: ResMut<State<AppState>>,
     mut foe: Query<&mut PlayerData, With<MyFoe>>,
     mut global_data: ResMut<GlobalData>,
+    pending_discover: Query<Entity, PendingDiscover>,
 ) {
+    // The timer keeps asking for the fight until a card is chosen
+    if pending_discover.iter().next().is_some() {
+        return;
+    }
     for _ in ev_fight.iter() {
         let mut foe = foe.single_mut().unwrap();
         foe.board = foe.ia.hand(&mut global_data);
@@ -1025,6 +1118,7 @@ fn drop_card(
                             } else if origin_slot.row == ShopSlots::SHOP {
                                 ev_coins.send(CoinsDiff(shop_values.buy, false));
                                 audio.play_in_channel(music.buy_card.clone(), &AudioChannel::new("SFX".to_owned()));
+                                commands.spawn().insert(CheckTriples);
                             } else {
                                 audio.play_in_channel(music.place_card.clone(), &AudioChannel::new("SFX".to_owned()));
                             }
@@ -1060,6 +1154,66 @@ fn drop_card(
     }
 }
 
+/// Copies in the hand or on the board merged into golden cards, three by three.
+/// Board copies come first, from left to right, then hand copies from left to right:
+/// the first three copies of a card are merged, then the next three, and so on.
+/// The golden card takes the slot of the first copy of its triple, i.e. the leftmost board slot
+/// if a copy was on the board, the leftmost hand slot otherwise. Golden cards aren't merged again,
+/// so every triple is found at once.
+fn triples<T: Copy>(cards: &[(T, Card, ShopSlot)]) -> Vec<[(T, Card, ShopSlot); 3]> {
+    let mut triples = vec![];
+    for &base_card in BaseCards::ALL.iter() {
+        let mut copies: Vec<(T, Card, ShopSlot)> = cards.iter()
+            .filter(|(_, card, slot)| card.base_card == base_card && !card.golden
+                && (slot.row == ShopSlots::HAND || slot.row == ShopSlots::BOARD))
+            .copied()
+            .collect();
+        copies.sort_by_key(|(_, _, slot)| (slot.row != ShopSlots::BOARD, slot.id));
+        for triple in copies.chunks_exact(3) {
+            triples.push([triple[0], triple[1], triple[2]]);
+        }
+    }
+    triples
+}
+
+/// Merges the copies of each card in the hand or on the board into golden cards, see `triples`.
+fn merge_triples(
+    mut commands: Commands,
+    checks: Query<Entity, With<CheckTriples>>,
+    cards: Query<(Entity, &Card, &ShopSlot, Option<&Draggable>)>,
+    (mut global_data, mut card_pool): (ResMut<GlobalData>, ResMut<CardPool>),
+    card_art: Res<CardArt>,
+    mut ev_new_card: EventWriter<NewCard>,
+    audio: Res<Audio>,
+    music: Res<AudioAssets>,
+) {
+    if checks.iter().next().is_none() {
+        return;
+    }
+    for e in checks.iter() {
+        commands.entity(e).despawn();
+    }
+
+    let cards: Vec<((Entity, bool), Card, ShopSlot)> = cards.iter()
+        .map(|(e, &card, &slot, draggable)| ((e, draggable.is_some()), card, slot))
+        .collect();
+    for triple in triples(&cards) {
+        for ((e, _), _, _) in triple.iter() {
+            commands.entity(*e).despawn_recursive();
+        }
+        let ((_, draggable), _, slot) = triple[0];
+        let merged = [triple[0].1, triple[1].1, triple[2].1];
+        let golden = Card::merge(&merged, global_data.next_card_id);
+        global_data.next_card_id += 1;
+        card_pool.merge(&merged, &golden);
+        let e = add_card(golden, slot, &mut commands, &card_art, &mut ev_new_card);
+        if draggable {
+            commands.entity(e).insert(Draggable { size: vec2(CARD_WIDTH / 2., CARD_HEIGHT / 2.) });
+        }
+        audio.play_in_channel(music.level_up.clone(), &AudioChannel::new("SFX".to_owned()));
+    }
+}
+
 fn sell_card(
     mut commands: Commands,
     shop_values: Res<ShopValues>,
@@ -1067,6 +1221,7 @@ fn sell_card(
     mut ev_coins: EventWriter<CoinsDiff>,
     mut ev_sold: EventWriter<SoldTrigger>,
     mut cards: Query<(Entity, &ShopSlot, &Card), With<Card>>,
+    mut card_pool: ResMut<CardPool>,
     audio: Res<Audio>,
     music: Res<AudioAssets>,
 ) {
@@ -1075,6 +1230,7 @@ fn sell_card(
             if e == transition.0 && slot.row == ShopSlots::SELL {
                 audio.play_in_channel(music.sell_card.clone(), &AudioChannel::new("SFX".to_owned()));
                 ev_sold.send(SoldTrigger(card.clone()));
+                card_pool.put_back(card);
                 commands.entity(transition.0).despawn_recursive();
                 ev_coins.send(CoinsDiff(shop_values.sell, false));
             }
@@ -1101,10 +1257,13 @@ fn on_exit(
     mut commands: Commands,
     cards: Query<(Entity, &Card, &ShopSlot)>,
     mut player_data: Query<&mut PlayerData, With<MySelf>>,
+    mut frozen_shop: ResMut<ShopFrozen>,
+    mut card_pool: ResMut<CardPool>,
 ) {
     let mut player_data = player_data.single_mut().expect("There should only be one player tagged with myself");
     let mut new_board: Vec<(u8, Card)> = vec![];
     let mut new_hand: Vec<(u8, Card)> = vec![];
+    let mut shop: Vec<(u8, Card)> = vec![];
     for (e, &card, slot) in cards.iter() {
         match slot.row {
             ShopSlots::BOARD => {
@@ -1113,18 +1272,25 @@ fn on_exit(
             ShopSlots::HAND => {
                 new_hand.push((slot.id, card));
             }
-            ShopSlots::SHOP => {}
+            ShopSlots::SHOP => {
+                shop.push((slot.id, card));
+            }
             ShopSlots::SELL => {}
         };
         commands.entity(e).despawn_recursive();
     }
+    // Frozen cards that were not bought stay out of the pool until the next shop
+    match frozen_shop.0 {
+        Some(_) => frozen_shop.0 = Some(shop),
+        None => shop.iter().for_each(|(_, card)| card_pool.put_back(card)),
+    }
     new_board.sort_by_key(|t| t.0);
     new_hand.sort_by_key(|t| t.0);
     player_data.board = new_board.iter().map(|t| t.1).collect();
     player_data.hand = new_hand.iter().map(|t| t.1).collect();
 }
 
-struct StartDraggableAt(f64);
+pub(crate) struct StartDraggableAt(pub f64);
 
 fn start_draggable(
     start_draggable_query: Query<(Entity, &StartDraggableAt)>,
@@ -1147,4 +1313,35 @@ fn start_draggable(
             can_refresh.0 = true;
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn six_copies_make_two_golden_cards() {
+        let slots = [
+            ShopSlot { row: ShopSlots::HAND, id: 3 },
+            ShopSlot { row: ShopSlots::BOARD, id: 4 },
+            ShopSlot { row: ShopSlots::HAND, id: 0 },
+            ShopSlot { row: ShopSlots::BOARD, id: 1 },
+            ShopSlot { row: ShopSlots::HAND, id: 1 },
+            ShopSlot { row: ShopSlots::SHOP, id: 0 },
+            ShopSlot { row: ShopSlots::HAND, id: 2 },
+        ];
+        let mut cards: Vec<(u32, Card, ShopSlot)> = slots.iter().enumerate()
+            .map(|(i, &slot)| (i as u32, Card::new(BaseCards::Mush2, i as u32), slot))
+            .collect();
+        cards.push((7, Card::new(BaseCards::Mush1, 7), ShopSlot { row: ShopSlots::BOARD, id: 0 }));
+
+        // Board copies first, then hand copies, from left to right; the copy in the shop is left out
+        let triples: Vec<(Vec<u32>, ShopSlot)> = triples(&cards).iter()
+            .map(|triple| (triple.iter().map(|(id, _, _)| *id).collect(), triple[0].2))
+            .collect();
+        assert_eq!(triples, vec![
+            (vec![3, 1, 2], ShopSlot { row: ShopSlots::BOARD, id: 1 }),
+            (vec![4, 6, 0], ShopSlot { row: ShopSlots::HAND, id: 1 }),
+        ]);
+    }
+}
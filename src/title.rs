@@ -4,9 +4,10 @@ use rand::Rng;
 use rand::distributions::Standard;
 
 use crate::{AppState, GlobalData, HEIGHT, MySelf, PlayerData, WIDTH};
-use crate::fight::fight_screen::MyFoe;
+use crate::fight::fight_screen::{MyFoe, ReplayViewer};
 use crate::data::font::TextStyles;
 use crate::data::loading::{AudioAssets, TextureAssets};
+use crate::shop::shop_manager::CardPool;
 use crate::ui::StateBackground;
 use crate::util::{Z_BACKGROUND, cleanup_system};
 
@@ -18,10 +19,12 @@ impl Plugin for TitlePlugin {
             .add_system_set(
                 SystemSet::on_enter(AppState::Title)
                     .with_system(display_title.system())
-                    .with_system(setup_data.system())
+                    // Players left by a replay
+                    .with_system(cleanup_system::<PlayerData>.system())
             )
             .add_system_set(
                 SystemSet::on_update(AppState::Title)
+                    .with_system(type_seed.system())
                     .with_system(click_to_shop.system())
             )
             .add_system_set(
@@ -35,12 +38,32 @@ impl Plugin for TitlePlugin {
 
 struct Title;
 
+struct SeedText;
+
+/// Digits of the seed of the next run, editable on the title screen.
+/// The random seed is replaced as soon as the player types.
+struct SeedInput {
+    digits: String,
+    typed: bool,
+}
+
+impl SeedInput {
+    /// `None` when the digits aren't a `u64`, e.g. when there are none
+    fn seed(&self) -> Option<u64> {
+        self.digits.parse().ok()
+    }
+}
+
+/// Digits of `u64::MAX`
+const MAX_SEED_DIGITS: usize = 20;
+
 fn display_title(
     mut commands: Commands,
     text_styles: Res<TextStyles>,
     handles: Res<TextureAssets>,
     audio: Res<Audio>,
     songs: Res<AudioAssets>,
+    replay_viewer: Option<Res<ReplayViewer>>,
 ) {
     audio.stop();
     audio.set_volume_in_channel(0.8, &AudioChannel::new("SFX".to_owned()));
@@ -57,7 +80,7 @@ fn display_title(
     }).insert(StateBackground);
 
     commands.spawn_bundle(Text2dBundle {
-        text: Text::with_section("Click to start",
+        text: Text::with_section(if replay_viewer.is_some() { "Click to watch the replay" } else { "Click to start" },
                                  text_styles.subtitle.clone(),
                                  TextAlignment {
                                      horizontal: HorizontalAlign::Center,
@@ -69,24 +92,98 @@ fn display_title(
         },
         ..Default::default()
     }).insert(Title);
+
+    let seed_input = SeedInput { digits: rand::random::<u64>().to_string(), typed: false };
+    commands.spawn_bundle(Text2dBundle {
+        text: Text::with_section(seed_text(&seed_input),
+                                 text_styles.love_bug_small.clone(),
+                                 TextAlignment {
+                                     horizontal: HorizontalAlign::Center,
+                                     ..Default::default()
+                                 }),
+        transform: Transform {
+            translation: Vec3::new(WIDTH / 2., HEIGHT / 2. + 70., 1.),
+            ..Default::default()
+        },
+        ..Default::default()
+    }).insert(Title).insert(SeedText);
+    commands.insert_resource(seed_input);
+}
+
+fn seed_text(seed_input: &SeedInput) -> String {
+    match seed_input.seed() {
+        Some(_) => format!("SEED {}\nType a seed to replay a run", seed_input.digits),
+        None => format!("SEED {} (invalid)\nType a seed to replay a run", seed_input.digits),
+    }
+}
+
+fn type_seed(
+    mut ev_char: EventReader<ReceivedCharacter>,
+    keys: Res<Input<KeyCode>>,
+    mut seed_input: ResMut<SeedInput>,
+    mut seed_text_query: Query<&mut Text, With<SeedText>>,
+) {
+    let mut changed = false;
+    let typed_digits: Vec<char> = ev_char.iter().map(|ev| ev.char).filter(|c| c.is_ascii_digit()).collect();
+    if !seed_input.typed && (keys.just_pressed(KeyCode::Back) || !typed_digits.is_empty()) {
+        seed_input.digits.clear();
+        seed_input.typed = true;
+        changed = true;
+    } else if keys.just_pressed(KeyCode::Back) {
+        seed_input.digits.pop();
+        changed = true;
+    }
+    for c in typed_digits {
+        if seed_input.digits.len() < MAX_SEED_DIGITS {
+            seed_input.digits.push(c);
+            changed = true;
+        }
+    }
+    if changed {
+        if let Ok(mut text) = seed_text_query.single_mut() {
+            text.sections[0].value = seed_text(&seed_input);
+        }
+    }
 }
 
 fn click_to_shop(
+    mut commands: Commands,
     mut app_state: ResMut<State<AppState>>,
     btn: Res<Input<MouseButton>>,
+    seed_input: Res<SeedInput>,
+    replay_viewer: Option<Res<ReplayViewer>>,
 ) {
     if btn.just_pressed(MouseButton::Left) {
-        app_state.set(AppState::Shop).unwrap();
+        if let Some(viewer) = replay_viewer {
+            setup_replay(&mut commands, &viewer);
+            app_state.set(AppState::Fight).unwrap();
+        } else if let Some(seed) = seed_input.seed() {
+            setup_data(&mut commands, seed);
+            app_state.set(AppState::Shop).unwrap();
+        }
     }
 }
 
+fn setup_replay(
+    commands: &mut Commands,
+    ReplayViewer(replay): &ReplayViewer,
+) {
+    let mut myself = PlayerData { id: replay.hb1.id, name: "YOU".to_string(), ..Default::default() };
+    myself.set_half_board(replay.hb1.clone());
+    commands.spawn().insert(myself).insert(MySelf);
+
+    let mut my_foe = PlayerData { id: replay.hb2.id, name: "FOE".to_string(), ..Default::default() };
+    my_foe.set_half_board(replay.hb2.clone());
+    commands.spawn().insert(my_foe).insert(MyFoe);
+
+    commands.insert_resource(GlobalData::from_seed(replay.seed));
+}
+
 fn setup_data(
-    mut commands: Commands,
+    commands: &mut Commands,
+    seed: u64,
 ) {
-    let mut global_data = GlobalData {
-        next_card_id: 0, // WARNING: the number of cards created before in this function
-        ..Default::default()
-    };
+    let mut global_data = GlobalData::from_seed(seed);
     commands.spawn().insert(
         PlayerData {
             id: 0,
@@ -105,4 +202,5 @@ fn setup_data(
         }).insert(MyFoe);
 
     commands.insert_resource(global_data);
+    commands.insert_resource(CardPool::default());
 }
\ No newline at end of file
@@ -71,13 +71,14 @@ pub(crate) fn init_popup(
 ) {
     for new_card in ev_new_card.iter() {
         let base_card = new_card.1.base_card;
+        let keywords = new_card.1.keywords.names();
         commands.entity(new_card.0).with_children(|parent| {
             parent
                 .spawn_bundle(Text2dBundle {
                     text: Text {
                         sections: vec![
                             TextSection {
-                                value: format!("{}\n", base_card.name().to_string()),
+                                value: if new_card.1.golden { format!("Golden {}\n", base_card.name()) } else { format!("{}\n", base_card.name()) },
                                 style: text_styles.love_bug_small.clone(),
                             },
                             TextSection {
@@ -90,6 +91,10 @@ pub(crate) fn init_popup(
                                 } else { "".to_string() },
                                 style: text_styles.bird_seed_small.clone(),
                             },
+                            TextSection {
+                                value: if keywords.is_empty() { "".to_string() } else { format!("{}\n\n", keywords.join(", ")) },
+                                style: text_styles.love_bug_small.clone(),
+                            },
                             TextSection {
                                 value: format!("{}\n\n", base_card.description().to_string()),
                                 style: text_styles.bird_seed_small.clone(),
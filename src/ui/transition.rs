@@ -154,6 +154,7 @@ fn update_translate_animation(
             let t = (t / duration) as f32;
             transform.translation = *start + *speed * easing::apply(f, t);
         } else {
+            transform.translation = *start + *speed;
             commands.entity(e).remove::<TranslationAnimation>();
             ev_transition.send(TransitionOver(e));
         }
@@ -12,6 +12,7 @@ pub struct PlayerHP;
 
 pub const Z_BACKGROUND: f32 = 0.;
 pub const Z_STATS: f32 = 0.1; // (+ 10., children of card)
+pub const Z_GOLDEN_FRAME: f32 = -0.05; // (+ 10., behind the card)
 pub const Z_CARD: f32 = 10.;
 pub const Z_ABILITY: f32 = 15.;
 pub const Z_CARD_SWITCH: f32 = 20.;
@@ -20,6 +21,7 @@ pub const Z_BOB: f32 = 100.;
 pub const Z_POPUP_BG: f32 = 120.;
 pub const Z_POPUP_TEXT: f32 = 121.;
 pub const Z_ANNOUNCEMENT_BG: f32 = 130.;
+pub const Z_DISCOVER: f32 = 131.;
 
 pub const ANIM_DURATION: f64 = 0.8;
 